@@ -0,0 +1,165 @@
+//! Playing from the terminal.
+
+use std::collections::HashMap;
+use async_trait::async_trait;
+use std::io::{Result, Error};
+use crate::{point::Point, side::Side};
+use crate::game::{Game, position::Position, terminal::{SharedTerminal, TerminalWrapper, Input, Prompt}};
+use crate::notation::pdn;
+use super::{Player, Action, ActionResult};
+
+/// Picks moves on the terminal board, with the mouse or the keyboard, or by typing them in. Uses
+/// the game's terminal, so it can only play games that have one.
+pub struct HumanPlayer {
+    terminal: Option<SharedTerminal>
+}
+
+impl HumanPlayer {
+    /// A player that picks up the terminal from the game when it starts.
+    pub fn new() -> HumanPlayer {
+        HumanPlayer {
+            terminal: None
+        }
+    }
+}
+
+impl Default for HumanPlayer {
+    fn default() -> Self {
+        HumanPlayer::new()
+    }
+}
+
+#[async_trait]
+impl Player for HumanPlayer {
+    async fn choose_action(&mut self, position: &Position, legal_moves: &HashMap<Point, Vec<Action>>) -> ActionResult {
+        let mut terminal_wrapper = self.terminal.as_ref().unwrap().lock().await; // Checked when the game started
+        let result = pick_action(position, legal_moves, &mut terminal_wrapper).await;
+        clear_overlay(&mut terminal_wrapper);
+        result
+    }
+
+    async fn game_started(&mut self, _side: Side, game: &Game) -> Result<()> {
+        self.terminal = game.terminal_wrapper.clone();
+        match self.terminal {
+            Some(_) => Ok(()),
+            None => Err(Error::new(std::io::ErrorKind::Unsupported, "Human players need a terminal to play on"))
+        }
+    }
+
+    fn is_human(&self) -> bool {
+        true
+    }
+}
+
+// Asks the player until they pick a move or do something else with their turn
+async fn pick_action(position: &Position, legal_moves: &HashMap<Point, Vec<Action>>, terminal_wrapper: &mut TerminalWrapper) -> ActionResult {
+    // Every legal move written out, for completing typed ones
+    let mut written_moves: Vec<String> = legal_moves.values().flatten().map(|action| pdn::action_to_string(action, position.board().width())).collect();
+    written_moves.sort();
+    terminal_wrapper.overlay.prompt = Some(Prompt::default());
+
+    let mut switched_to = None; // Another piece picked while one was already selected
+    'select: loop {
+        let piece_cords = match switched_to.take() {
+            Some(point) => point,
+            None => {
+                // Highlight all pieces that can move. If a capture is available, get_all_moves
+                // only returns the pieces that can capture, so only those will be highlighted
+                terminal_wrapper.overlay.highlighted = legal_moves.keys().cloned().collect();
+                terminal_wrapper.overlay.message = "Select which piece you want to move, or type the whole move like 11-15\n\
+                    Click or use the arrow keys and Enter to select, Tab completes typed moves (u to undo, r to redo, g to resign, q to quit)".to_string();
+                terminal_wrapper.draw(position.board()).await.unwrap();
+
+                // The only way to stop getting input is to quit
+                match terminal_wrapper.next_choice(position.board(), &written_moves).await {
+                    Ok(Input::Click(point)) => point,
+                    Ok(Input::Typed(text)) => match typed_action(&text, position, terminal_wrapper) {
+                        Some(action) => return ActionResult::TookAction(action),
+                        None => continue
+                    },
+                    Ok(Input::Resign) => return ActionResult::Resigned,
+                    Ok(Input::Undo) => return ActionResult::Undo,
+                    Ok(Input::Redo) => return ActionResult::Redo,
+                    Ok(_) => continue, // Nothing else does anything until a piece is picked
+                    Err(_) => return ActionResult::Quit
+                }
+            }
+        };
+
+        let maybe_piece = position.board().piece_at(&piece_cords);
+        if !position.side_to_move().piece_is_friendly(&maybe_piece) {
+            continue; // Pick a new piece if we picked a spot that doesnt have one of our pieces
+        }
+
+        let valid_moves = match legal_moves.get(&piece_cords) {
+            Some(moves) => moves,
+            None => continue // Pick a new piece if the one we picked cant make any valid moves
+        };
+
+        // Click through each square the piece lands on, one hop at a time
+        let mut path: Vec<Point> = Vec::new();
+        loop {
+            // Only the moves that follow the hops picked so far are still possible
+            let remaining: Vec<&Action> = valid_moves.iter().filter(|action| action.path.starts_with(&path)).collect();
+            if let Some(action) = remaining.iter().find(|action| action.path == path) {
+                return ActionResult::TookAction((*action).clone());
+            }
+
+            let next_hops: Vec<Point> = remaining.iter().map(|action| action.path[path.len()].clone()).collect();
+
+            // Highlight the hops taken so far and every valid next hop
+            terminal_wrapper.overlay.highlighted = next_hops.iter().chain(path.iter()).cloned().collect();
+            terminal_wrapper.overlay.message = if path.is_empty() {
+                "Select where you'd like to move the piece (pick another piece to switch, or Esc to put this one down)".to_string()
+            }
+            else {
+                "Select where you'd like to jump next (pick another piece to switch, or Esc to start again)".to_string()
+            };
+            terminal_wrapper.draw(position.board()).await.unwrap();
+
+            let chosen_hop = match terminal_wrapper.next_choice(position.board(), &written_moves).await {
+                Ok(Input::Click(point)) => point,
+                Ok(Input::Typed(text)) => match typed_action(&text, position, terminal_wrapper) {
+                    Some(action) => return ActionResult::TookAction(action),
+                    None => continue
+                },
+                Ok(Input::Cancel) => continue 'select, // Start over with a different piece
+                Ok(Input::Resign) => return ActionResult::Resigned,
+                Ok(Input::Undo) => return ActionResult::Undo,
+                Ok(Input::Redo) => return ActionResult::Redo,
+                Ok(_) => continue,
+                Err(_) => return ActionResult::Quit
+            };
+
+            // Picking a piece that can move, rather than somewhere this one can go, switches
+            // to that piece. Picking this one again starts its move over
+            if !next_hops.contains(&chosen_hop) && legal_moves.contains_key(&chosen_hop) {
+                switched_to = Some(chosen_hop);
+                continue 'select;
+            }
+            if !next_hops.contains(&chosen_hop) {
+                continue; // Pick a new hop if we picked a spot that isnt a valid one
+            }
+
+            path.push(chosen_hop);
+        }
+    }
+}
+
+// The legal move the player typed, or None if it isn't one, in which case the prompt says why
+fn typed_action(text: &str, position: &Position, terminal_wrapper: &mut TerminalWrapper) -> Option<Action> {
+    match pdn::parse_action(text, position) {
+        Ok(action) => Some(action),
+        Err(error) => {
+            terminal_wrapper.overlay.prompt.as_mut().unwrap().hint = error.to_string();
+            None
+        }
+    }
+}
+
+// Nothing needs to be shown once the turn is over, however it ended
+fn clear_overlay(terminal_wrapper: &mut TerminalWrapper) {
+    terminal_wrapper.overlay.highlighted.clear();
+    terminal_wrapper.overlay.message.clear();
+    terminal_wrapper.overlay.prompt = None;
+}
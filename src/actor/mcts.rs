@@ -0,0 +1,52 @@
+//! Monte Carlo tree search.
+
+use std::collections::HashMap;
+use async_trait::async_trait;
+use std::io::Result;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use crate::{point::Point, side::Side};
+use crate::game::{Game, position::Position};
+use crate::mcts::{MctsConfig, Tree};
+use super::{Player, Action, ActionResult, seeded_rng};
+
+/// Monte Carlo tree search.
+pub struct MctsPlayer {
+    /// How long to search and how to play out games.
+    pub config: MctsConfig,
+    tree: Option<Tree>, // Kept between turns so each search can build on the earlier ones
+    rng: StdRng
+}
+
+impl MctsPlayer {
+    /// A player with an empty tree, which reseeds itself from the game when it starts.
+    pub fn new(config: MctsConfig) -> MctsPlayer {
+        MctsPlayer {
+            config,
+            tree: None,
+            rng: StdRng::from_entropy()
+        }
+    }
+}
+
+#[async_trait]
+impl Player for MctsPlayer {
+    async fn choose_action(&mut self, position: &Position, _legal_moves: &HashMap<Point, Vec<Action>>) -> ActionResult {
+        // Pick up from last turn's tree if it got as far as the current board
+        let mut tree = match self.tree.take().and_then(|tree| tree.advance(position)) {
+            Some(tree) => tree,
+            None => Tree::new(position)
+        };
+
+        // Search on a copy, since the search walks the position up and down the tree
+        let mut position = position.clone();
+        let chosen_move = tree.search(&mut position, self.config, self.rng.gen()).await;
+        self.tree = Some(tree);
+
+        ActionResult::TookAction(chosen_move.unwrap())
+    }
+
+    async fn game_started(&mut self, side: Side, game: &Game) -> Result<()> {
+        self.rng = seeded_rng(game.seed, side);
+        Ok(())
+    }
+}
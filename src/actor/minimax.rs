@@ -0,0 +1,35 @@
+//! Alpha-beta search.
+
+use std::collections::HashMap;
+use async_trait::async_trait;
+use std::io::Result;
+use crate::{point::Point, side::Side, rules::DrawRules};
+use crate::game::{Game, position::Position};
+use crate::search::{self, SearchConfig};
+use super::{Player, Action, ActionResult};
+
+/// Searches ahead with alpha-beta pruning.
+pub struct MinimaxPlayer {
+    /// How deep and how long to search.
+    pub config: SearchConfig,
+    /// The game's draw rules, so the search knows which lines end in a draw. Set when the game
+    /// starts.
+    pub draw_rules: DrawRules
+}
+
+#[async_trait]
+impl Player for MinimaxPlayer {
+    async fn choose_action(&mut self, position: &Position, _legal_moves: &HashMap<Point, Vec<Action>>) -> ActionResult {
+        // Searching doesn't yield, so run it off the async threads
+        let mut position = position.clone();
+        let (config, draw_rules) = (self.config, self.draw_rules);
+        let chosen_move = tokio::task::spawn_blocking(move || search::best_action(&mut position, config, draw_rules)).await.unwrap();
+
+        ActionResult::TookAction(chosen_move.unwrap())
+    }
+
+    async fn game_started(&mut self, _side: Side, game: &Game) -> Result<()> {
+        self.draw_rules = game.draw_rules;
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+//! The players. Anything that implements [`Player`] can play a side, and [`ActorType`] picks one
+//! of the built in ones.
+
+pub mod human;
+pub mod random;
+pub mod most_kills;
+pub mod minimax;
+pub mod mcts;
+pub mod simulated;
+
+use std::collections::HashMap;
+use async_trait::async_trait;
+use rand::{SeedableRng, rngs::StdRng};
+
+use crate::{side::Side, rules::DrawRules};
+use crate::point::Point;
+use std::io::Result;
+use crate::game::{Game, GameResult, position::Position};
+use crate::search::SearchConfig;
+use crate::mcts::MctsConfig;
+
+/// A whole move, including every jump of a jump chain.
+#[derive(Eq, PartialEq, Hash, Clone, Debug, PartialOrd, Ord)]
+pub struct Action {
+    /// Where the moving piece starts.
+    pub from: Point,
+    /// Every square the piece lands on, in order. Jump chains have more than one.
+    pub path: Vec<Point>,
+    /// Every enemy piece jumped over along the way.
+    pub captures: Vec<Point>
+}
+
+impl Action {
+    /// The square the piece ends up on.
+    pub fn to(&self) -> &Point {
+        self.path.last().unwrap()
+    }
+
+    /// Whether the move jumps anything.
+    pub fn is_capture(&self) -> bool {
+        !self.captures.is_empty()
+    }
+}
+
+/// What a player decided to do on their turn.
+#[derive(PartialEq)]
+pub enum ActionResult {
+    /// Make this move. It has to be one of the legal ones.
+    TookAction(Action),
+    /// Give up, losing the game.
+    Resigned,
+    /// Take back moves up to this player's previous turn.
+    Undo,
+    /// Put back moves that were taken back.
+    Redo,
+    /// Stop the game without finishing it, like when the player quits.
+    Quit
+}
+
+/// Anything that can play a side. The game asks for a move whenever it's this player's turn, and
+/// lets it know about everything else that happens in case it wants to keep track. The hooks do
+/// nothing unless a player needs them.
+#[async_trait]
+pub trait Player: Send {
+    /// Picks what to do this turn. The legal moves are never empty, since the game ends before
+    /// then.
+    async fn choose_action(&mut self, position: &Position, legal_moves: &HashMap<Point, Vec<Action>>) -> ActionResult;
+
+    /// Called once before the first move, with the side this player is playing. Players that
+    /// can't play this game, like a human with no way of seeing it, return an error to stop it
+    /// before it starts.
+    async fn game_started(&mut self, _side: Side, _game: &Game) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called after the other side makes a move, with the position after it.
+    async fn opponent_moved(&mut self, _action: &Action, _position: &Position) {}
+
+    /// Called once the game is over, before the players are dropped.
+    async fn game_ended(&mut self, _result: &GameResult) {}
+
+    /// Undoing stops when it's a human's turn again, so they don't have to undo the bot's moves
+    /// one at a time.
+    fn is_human(&self) -> bool {
+        false
+    }
+}
+
+/// A random number generator for a player. The same seed always gives the same choices, but each
+/// side gets its own stream so two random players don't just mirror each other. Players without a
+/// seed are different every game.
+pub fn seeded_rng(seed: Option<u64>, side: Side) -> StdRng {
+    match seed {
+        // Mixing before adding the side keeps seeds next to each other from sharing streams, since
+        // tournaments give each game the next seed along
+        Some(seed) => StdRng::seed_from_u64(splitmix(splitmix(seed).wrapping_add(side as u64))),
+        None => StdRng::from_entropy()
+    }
+}
+
+// One step of SplitMix64, which scrambles every bit of the input into the output
+fn splitmix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// The players that are quick enough to play out every simulated game for [`ActorType::Simulated`].
+#[derive(PartialEq, Clone, Copy)]
+pub enum SimulatedActorType {
+    /// See [`ActorType::Random`].
+    Random,
+    /// See [`ActorType::MostKills`].
+    MostKills {
+        /// Crown a piece when it doesn't mean taking fewer.
+        prefer_crowning: bool
+    }
+}
+
+/// The built in players, for picking one without having to build it yet.
+#[derive(Clone, Copy)]
+pub enum ActorType {
+    /// Picks moves on the terminal board, with the mouse or the keyboard, or by typing them in.
+    Human,
+    /// Picks a random piece that can move, then a random move for it.
+    Random,
+    /// Greedily takes as many pieces as it can.
+    MostKills {
+        /// Crown a piece when it doesn't mean taking fewer.
+        prefer_crowning: bool
+    },
+    /// Searches ahead with alpha-beta pruning.
+    Minimax(SearchConfig),
+    /// Monte Carlo tree search.
+    Mcts(MctsConfig),
+    /// Plays every move out to the end and picks the one that won quickest.
+    Simulated(SimulatedActorType)
+}
+
+impl From<SimulatedActorType> for ActorType {
+    fn from(input: SimulatedActorType) -> Self {
+        match input {
+            SimulatedActorType::Random => ActorType::Random,
+            SimulatedActorType::MostKills { prefer_crowning } => ActorType::MostKills { prefer_crowning }
+        }
+    }
+}
+
+impl ActorType {
+    /// Builds a fresh player of this type.
+    pub fn player(self) -> Box<dyn Player> {
+        match self {
+            ActorType::Human => Box::new(human::HumanPlayer::new()),
+            ActorType::Random => Box::new(random::RandomPlayer::new()),
+            ActorType::MostKills { prefer_crowning } => Box::new(most_kills::MostKillsPlayer { prefer_crowning }),
+            ActorType::Minimax(config) => Box::new(minimax::MinimaxPlayer { config, draw_rules: DrawRules::default() }),
+            ActorType::Mcts(config) => Box::new(mcts::MctsPlayer::new(config)),
+            ActorType::Simulated(playout) => Box::new(simulated::SimulatedPlayer::new(playout))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn neighbouring_seeds_dont_share_streams() {
+        let streams: Vec<u64> = (0..4).flat_map(|seed| [Side::Red, Side::Blue].map(|side| seeded_rng(Some(seed), side).gen())).collect();
+        for (i, stream) in streams.iter().enumerate() {
+            assert!(!streams[i + 1..].contains(stream));
+        }
+        assert_eq!(seeded_rng(Some(3), Side::Blue).gen::<u64>(), streams[7]);
+    }
+}
@@ -0,0 +1,31 @@
+//! Greedy captures.
+
+use std::collections::HashMap;
+use async_trait::async_trait;
+use crate::point::Point;
+use crate::game::position::Position;
+use super::{Player, Action, ActionResult};
+
+/// Greedily takes as many pieces as it can.
+pub struct MostKillsPlayer {
+    /// Crown a piece when it doesn't mean taking fewer.
+    pub prefer_crowning: bool
+}
+
+#[async_trait]
+impl Player for MostKillsPlayer {
+    async fn choose_action(&mut self, position: &Position, legal_moves: &HashMap<Point, Vec<Action>>) -> ActionResult {
+        // Take the most pieces possible, then (if we care) get crowned if we can. Ties go to
+        // whichever action sorts first so the same board always gets the same move
+        let chosen_move = legal_moves.values().flatten().max_by(|a, b| {
+            let a_crowns = self.prefer_crowning && position.board().crowns(a);
+            let b_crowns = self.prefer_crowning && position.board().crowns(b);
+
+            a.captures.len().cmp(&b.captures.len())
+                .then(a_crowns.cmp(&b_crowns))
+                .then(b.cmp(a))
+        }).unwrap();
+
+        ActionResult::TookAction(chosen_move.clone())
+    }
+}
@@ -0,0 +1,46 @@
+//! Random moves.
+
+use std::collections::HashMap;
+use async_trait::async_trait;
+use std::io::Result;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use crate::{point::Point, side::Side};
+use crate::game::{Game, position::Position};
+use super::{Player, Action, ActionResult, seeded_rng};
+
+/// Picks a random piece that can move, then a random move for it.
+pub struct RandomPlayer {
+    rng: StdRng
+}
+
+impl RandomPlayer {
+    /// A player that reseeds itself from the game when it starts.
+    pub fn new() -> RandomPlayer {
+        RandomPlayer {
+            rng: StdRng::from_entropy()
+        }
+    }
+}
+
+impl Default for RandomPlayer {
+    fn default() -> Self {
+        RandomPlayer::new()
+    }
+}
+
+#[async_trait]
+impl Player for RandomPlayer {
+    async fn choose_action(&mut self, _position: &Position, legal_moves: &HashMap<Point, Vec<Action>>) -> ActionResult {
+        // Sort the pieces so the same seed always picks the same one
+        let mut pieces = legal_moves.keys().collect::<Vec<&Point>>();
+        pieces.sort();
+
+        let moves = &legal_moves[pieces[self.rng.gen_range(0..pieces.len())]];
+        ActionResult::TookAction(moves[self.rng.gen_range(0..moves.len())].clone())
+    }
+
+    async fn game_started(&mut self, side: Side, game: &Game) -> Result<()> {
+        self.rng = seeded_rng(game.seed, side);
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+//! Playing every move out to the end.
+
+use std::collections::HashMap;
+use async_trait::async_trait;
+use std::io::Result;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use crate::{point::Point, side::Side, rules::DrawRules};
+use crate::game::{Game, GameResult, position::Position};
+use super::{Player, Action, ActionResult, ActorType, SimulatedActorType, seeded_rng};
+
+/// Plays every move out to the end, with both sides played by the playout type, and picks the move
+/// that won quickest.
+pub struct SimulatedPlayer {
+    /// Who plays both sides of the simulated games.
+    pub playout: SimulatedActorType,
+    draw_rules: DrawRules, // The simulated games finish the same way the real one does
+    rng: StdRng
+}
+
+impl SimulatedPlayer {
+    /// A player that picks up the game's draw rules and seed when it starts.
+    pub fn new(playout: SimulatedActorType) -> SimulatedPlayer {
+        SimulatedPlayer {
+            playout,
+            draw_rules: DrawRules::default(),
+            rng: StdRng::from_entropy()
+        }
+    }
+}
+
+#[async_trait]
+impl Player for SimulatedPlayer {
+    async fn choose_action(&mut self, position: &Position, legal_moves: &HashMap<Point, Vec<Action>>) -> ActionResult {
+        // Go through the actions in order and give each simulated game its own seed, so the same
+        // seed always simulates the same games however they get scheduled
+        let mut actions: Vec<&Action> = legal_moves.values().flatten().collect();
+        actions.sort();
+
+        let mut futures = Vec::new();
+        for action in actions {
+            let mut sim_game = Game::from_position(position.clone(), None).unwrap();
+            sim_game.draw_rules = self.draw_rules;
+            sim_game.seed = Some(self.rng.gen());
+            futures.push((action.clone(), tokio::spawn(simulate_action(sim_game, action.clone(), self.playout))));
+        }
+
+        let mut results = Vec::new();
+        for future in futures {
+            results.push((future.0, future.1.await.unwrap()));
+        }
+
+        let side = position.side_to_move();
+        let winning_results = results.iter().filter(|result| {
+            result.1.winner == Some(side)
+        });
+        let mut drawn_results = results.iter().filter(|result| {
+            result.1.winner.is_none()
+        });
+
+        let quickest_win = match winning_results.min_by_key(|result| result.1.moves) {
+            Some(x) => x,
+            None => match drawn_results.next() { // If min returns None, there are no winning games.
+                Some(x) => x,                    // Thus, settle for a draw if there is one,
+                None => results.iter().max_by_key(|result| result.1.moves).unwrap() // or else pick the one where we lose in the most moves
+            }
+        };
+
+        ActionResult::TookAction(quickest_win.0.clone())
+    }
+
+    async fn game_started(&mut self, side: Side, game: &Game) -> Result<()> {
+        self.draw_rules = game.draw_rules;
+        self.rng = seeded_rng(game.seed, side);
+        Ok(())
+    }
+}
+
+// Simulates the result of an action, and returns how many steps it took to get to that result
+async fn simulate_action(mut game: Game, action: Action, playout: SimulatedActorType) -> GameResult {
+    game.position.make_move(&action);
+    let playout = ActorType::from(playout);
+    game.play(playout.player(), playout.player()).await.unwrap().unwrap()
+}
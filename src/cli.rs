@@ -0,0 +1,215 @@
+use std::{path::PathBuf, time::Duration};
+use clap::{Parser, Subcommand, ValueEnum, CommandFactory, error::ErrorKind};
+use checkers::actor::{ActorType, SimulatedActorType};
+use checkers::rules::Ruleset;
+use checkers::search::{self, SearchConfig};
+use checkers::mcts::{MctsConfig, Rollout};
+
+// How deep the alpha-beta search can go when it's only limited by time
+const TIMED_SEARCH_DEPTH: u8 = 64;
+
+#[derive(Parser)]
+#[command(about = "Play checkers in the terminal, against other people or against bots")]
+#[command(after_help = "Exit codes: 0 when the game ends or is quit, 1 if something went wrong while running, 2 for invalid arguments")]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub game: GameOptions,
+
+    #[arg(long, value_enum, default_value = "human", help = "Who plays Red, which is Black. Black moves first in American and pool checkers, White in the others")]
+    pub red: ActorKind,
+
+    #[arg(long, value_enum, default_value = "alpha-beta", help = "Who plays Blue, which is White")]
+    pub blue: ActorKind,
+
+    #[arg(long, value_name = "FILE", help = "Save the game as PDN once it's over")]
+    pub output: Option<PathBuf>,
+
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["fen", "rules", "width", "height"], help = "Carry on an unfinished game saved as PDN. Its rules and board size are used")]
+    pub load: Option<PathBuf>,
+
+    #[arg(long, help = "Start from a position given as FEN instead of the usual setup")]
+    pub fen: Option<String>,
+
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["load", "fen", "output"], help = "Step through a game saved as PDN instead of playing one")]
+    pub replay: Option<PathBuf>,
+
+    #[arg(long, conflicts_with_all = ["replay", "headless"], help = "Step through the game on the board once it's over")]
+    pub review: bool,
+
+    #[arg(long, conflicts_with = "replay", help = "Play without the terminal board and print the result as JSON. Both players have to be bots")]
+    pub headless: bool
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    #[command(about = "Play bots against each other and compare how they do")]
+    Tournament(TournamentArgs)
+}
+
+#[derive(clap::Args)]
+pub struct TournamentArgs {
+    #[command(flatten)]
+    pub game: GameOptions,
+
+    #[arg(long, value_enum, value_delimiter = ',', required = true, help = "The bots to play, separated by commas. Every bot plays every other one")]
+    pub actors: Vec<ActorKind>,
+
+    #[arg(long, default_value_t = 10, help = "How many games each pair of bots plays, taking turns to be Red")]
+    pub games: usize,
+
+    #[arg(long, help = "How many games to play at once, defaults to the number of CPUs")]
+    pub parallel: Option<usize>
+}
+
+// Options that apply to every game, whether it's a single game or part of a tournament
+#[derive(clap::Args)]
+pub struct GameOptions {
+    #[arg(long, help = "Board width, defaults to the rules' usual size")]
+    pub width: Option<u8>,
+
+    #[arg(long, help = "Board height, defaults to the rules' usual size")]
+    pub height: Option<u8>,
+
+    #[arg(long, help = "Seed for the bots' random choices, so the same bots play the same game every time. Bots limited by --time can still vary")]
+    pub seed: Option<u64>,
+
+    #[arg(long, default_value = "american", value_parser = parse_rules, help = "Which variant's rules to play by (american, international, russian, brazilian or pool)")]
+    pub rules: Ruleset,
+
+    #[arg(long, help = "How many moves ahead the alpha-beta bot looks")]
+    pub depth: Option<u8>,
+
+    #[arg(long, value_name = "SECONDS", value_parser = parse_seconds, help = "How long the searching bots get to think about each move")]
+    pub time: Option<Duration>
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug)]
+pub enum ActorKind {
+    #[value(alias = "h", help = "Pick moves with the mouse or the keyboard, or type them in like 11-15")]
+    Human,
+    #[value(alias = "r", help = "Picks any legal move")]
+    Random,
+    #[value(alias = "m", help = "Takes as many pieces as it can")]
+    MostKills,
+    #[value(alias = "mc", help = "Takes as many pieces as it can, then gets crowned if it can")]
+    MostKillsCrowning,
+    #[value(alias = "a", help = "Searches ahead with alpha-beta pruning")]
+    AlphaBeta,
+    #[value(alias = "t", help = "Monte Carlo tree search with random playouts")]
+    Mcts,
+    #[value(alias = "th", help = "Monte Carlo tree search with playouts that look one move ahead")]
+    MctsHeuristic,
+    #[value(alias = "s", help = "Plays every move out to the end with random games and picks the quickest win")]
+    Simulated,
+    #[value(alias = "sm", help = "Plays every move out to the end with most kills games and picks the quickest win")]
+    SimulatedMostKills
+}
+
+impl ActorKind {
+    pub fn name(&self) -> String {
+        self.to_possible_value().unwrap().get_name().to_string()
+    }
+}
+
+// Exits with a usage error
+fn invalid(kind: ErrorKind, message: impl std::fmt::Display) -> ! {
+    Args::command().error(kind, message).exit()
+}
+
+impl Args {
+    // Parses the command line, exiting with a usage error if the options don't make a playable
+    // game
+    pub fn parse_and_validate() -> Args {
+        let args = Args::parse();
+
+        match &args.command {
+            Some(Command::Tournament(tournament)) => {
+                tournament.game.validate();
+
+                if tournament.actors.len() < 2 {
+                    invalid(ErrorKind::TooFewValues, "a tournament needs at least two bots");
+                }
+                if tournament.actors.contains(&ActorKind::Human) {
+                    invalid(ErrorKind::InvalidValue, "only bots can play in tournaments");
+                }
+                if tournament.actors.iter().enumerate().any(|(index, actor)| tournament.actors[..index].contains(actor)) {
+                    invalid(ErrorKind::ValueValidation, "each bot can only be in the tournament once");
+                }
+                if tournament.games == 0 || tournament.parallel == Some(0) {
+                    invalid(ErrorKind::ValueValidation, "--games and --parallel must be at least 1");
+                }
+            },
+            None => {
+                args.game.validate();
+
+                if args.headless && (args.red == ActorKind::Human || args.blue == ActorKind::Human) {
+                    invalid(ErrorKind::ArgumentConflict, "--headless needs bots for both players, so set --red and --blue");
+                }
+            }
+        }
+
+        args
+    }
+}
+
+impl GameOptions {
+    fn validate(&self) {
+        if let Some(problem) = self.ruleset().size_problem() {
+            invalid(ErrorKind::ValueValidation, problem);
+        }
+        if self.depth == Some(0) {
+            invalid(ErrorKind::ValueValidation, "the depth must be at least 1");
+        }
+    }
+
+    // The chosen rules, with the board size swapped in if one was given
+    pub fn ruleset(&self) -> Ruleset {
+        Ruleset {
+            width: self.width.unwrap_or(self.rules.width),
+            height: self.height.unwrap_or(self.rules.height),
+            ..self.rules
+        }
+    }
+
+    pub fn actor_type(&self, kind: ActorKind) -> ActorType {
+        // Searches with a time limit keep going deeper until they run out of time, unless they're
+        // also given a depth
+        let search = SearchConfig {
+            depth: self.depth.unwrap_or(if self.time.is_some() { TIMED_SEARCH_DEPTH } else { SearchConfig::default().depth }),
+            time_limit: self.time,
+            ..SearchConfig::default()
+        };
+        let mcts = MctsConfig {
+            iterations: if self.time.is_some() { None } else { MctsConfig::default().iterations },
+            time_limit: self.time,
+            ..MctsConfig::default()
+        };
+
+        match kind {
+            ActorKind::Human => ActorType::Human,
+            ActorKind::Random => ActorType::Random,
+            ActorKind::MostKills => ActorType::MostKills { prefer_crowning: false },
+            ActorKind::MostKillsCrowning => ActorType::MostKills { prefer_crowning: true },
+            ActorKind::AlphaBeta => ActorType::Minimax(search),
+            ActorKind::Mcts => ActorType::Mcts(mcts),
+            ActorKind::MctsHeuristic => ActorType::Mcts(MctsConfig { rollout: Rollout::Heuristic(search::weighted), ..mcts }),
+            ActorKind::Simulated => ActorType::Simulated(SimulatedActorType::Random),
+            ActorKind::SimulatedMostKills => ActorType::Simulated(SimulatedActorType::MostKills { prefer_crowning: true })
+        }
+    }
+}
+
+fn parse_rules(name: &str) -> Result<Ruleset, String> {
+    Ruleset::from_name(name).ok_or_else(|| format!("unknown rules \"{}\" (expected american, international, russian, brazilian or pool)", name))
+}
+
+fn parse_seconds(text: &str) -> Result<Duration, String> {
+    match text.parse::<f64>() {
+        Ok(seconds) if seconds > 0.0 && seconds.is_finite() => Ok(Duration::from_secs_f64(seconds)),
+        _ => Err(format!("\"{}\" isn't a positive number of seconds", text))
+    }
+}
@@ -1,172 +1,522 @@
-use crossterm::Result;
-use crate::{piece::Piece, side::Side, point::Point, actor::Action};
+//! Where the pieces are, and the moves they can make.
+
+use std::io::{Result, Error};
+use crate::{piece::Piece, side::Side, point::Point, actor::Action, rules::{Ruleset, MidChainPromotion}};
 use std::collections::HashMap;
 
-#[derive(Clone)]
-pub struct BoardState {
-    pub piece: Option<Piece>,
-    pub highlighted: bool
-}
+/// Each square gets one bit, numbered row by row from the top left, so boards can have up to 128
+/// squares.
+pub const MAX_SQUARES: usize = u128::BITS as usize;
 
+/// The pieces on the board. This doesn't know whose turn it is or how the game got here, see
+/// [`Position`](super::position::Position) for that.
 #[derive(Clone)]
 pub struct Board {
-    pub width: u8,
-    pub height: u8,
-    pub state: Vec<Vec<BoardState>>
+    rules: Ruleset, // Also says how big the board is, which the bits are laid out by
+    red: u128, // Squares with red pieces on them
+    blue: u128, // Squares with blue pieces on them
+    kings: u128 // Squares with crowned pieces on them, of either side
 }
 
 impl Board {
-    pub fn new(width: u8, height: u8) -> Result<Board> {
-        let mut board = Board {
-            width,
-            height,
-            state: Vec::default(),
-        };
+    /// A board with both sides' pieces in their starting places. Errors if the board would have
+    /// more than [`MAX_SQUARES`] squares.
+    pub fn new(rules: Ruleset) -> Result<Board> {
+        let mut board = Board::empty(rules)?;
 
-        let mut x: u8 = 0;
-        while x < board.width {
-            board.state.push(Vec::default());
-
-            let mut y: u8 = 0;
-            while y < board.height {
-                let piece;
-                if (x % 2) == (y % 2) {
-                    if y <= 2 {
-                        piece = Some(Piece { side: Side::Red, crowned: false});
-                    }
-                    else if y >= (board.height - 3) {
-                        piece = Some(Piece { side: Side::Blue, crowned: false});
-                    }
-                    else {
-                        piece = None;
-                    }
-                }
-                else {
-                    piece = None
+        for x in 0..board.width() {
+            for y in 0..board.height() {
+                if (x % 2) != (y % 2) {
+                    continue;
                 }
 
-                board.state[x as usize].push(BoardState {
-                    piece,
-                    highlighted: false
-                });
-                
-                y += 1;
+                if y < rules.piece_rows {
+                    board.set_piece(&Point { x, y }, Some(Piece { side: Side::Red, crowned: false }));
+                }
+                else if y >= (board.height() - rules.piece_rows) {
+                    board.set_piece(&Point { x, y }, Some(Piece { side: Side::Blue, crowned: false }));
+                }
             }
-            x += 1;
         }
 
         Ok(board)
     }
 
-    pub fn valid_moves(&self, acting_piece_cords: &Point) -> Result<Vec<Point>> {
+    /// A board with no pieces on it, for setting up positions by hand.
+    pub fn empty(rules: Ruleset) -> Result<Board> {
+        if rules.width as usize * rules.height as usize > MAX_SQUARES {
+            return Err(Error::new(std::io::ErrorKind::InvalidInput, format!("Boards can have at most {} squares", MAX_SQUARES)));
+        }
+
+        Ok(Board {
+            rules,
+            red: 0,
+            blue: 0,
+            kings: 0
+        })
+    }
+
+    /// How many squares across the board is.
+    pub fn width(&self) -> u8 {
+        self.rules.width
+    }
+
+    /// How many squares tall the board is.
+    pub fn height(&self) -> u8 {
+        self.rules.height
+    }
+
+    /// The rules the pieces move by.
+    pub fn rules(&self) -> Ruleset {
+        self.rules
+    }
+
+    fn bit(&self, point: &Point) -> u128 {
+        1 << (point.y as u32 * self.width() as u32 + point.x as u32)
+    }
+
+    fn point(&self, index: u32) -> Point {
+        Point {
+            x: (index % self.width() as u32) as u8,
+            y: (index / self.width() as u32) as u8
+        }
+    }
+
+    fn side_bits(&self, side: Side) -> u128 {
+        match side {
+            Side::Red => self.red,
+            Side::Blue => self.blue
+        }
+    }
+
+    /// The piece on the square, if there is one.
+    pub fn piece_at(&self, point: &Point) -> Option<Piece> {
+        let bit = self.bit(point);
+        let side = if self.red & bit != 0 {
+            Side::Red
+        }
+        else if self.blue & bit != 0 {
+            Side::Blue
+        }
+        else {
+            return None;
+        };
+
+        Some(Piece { side, crowned: self.kings & bit != 0 })
+    }
+
+    /// Puts a piece on the square, replacing whatever was there. None clears it.
+    pub fn set_piece(&mut self, point: &Point, piece: Option<Piece>) {
+        let bit = self.bit(point);
+        self.red &= !bit;
+        self.blue &= !bit;
+        self.kings &= !bit;
+
+        if let Some(piece) = piece {
+            match piece.side {
+                Side::Red => self.red |= bit,
+                Side::Blue => self.blue |= bit
+            }
+            if piece.crowned {
+                self.kings |= bit;
+            }
+        }
+    }
+
+    /// Every piece on the board along with where it is.
+    pub fn pieces(&self) -> impl Iterator<Item = (Point, Piece)> + '_ {
+        BitIter(self.red | self.blue).map(|index| {
+            let point = self.point(index);
+            let piece = self.piece_at(&point).unwrap();
+            (point, piece)
+        })
+    }
+
+    /// Every piece on the board, which is enough to tell whether two positions are the same.
+    pub fn position_key(&self) -> (u128, u128, u128) {
+        (self.red, self.blue, self.kings)
+    }
+
+    /// How many pieces the side has left.
+    pub fn count_pieces(&self, side: Side) -> usize {
+        self.side_bits(side).count_ones() as usize
+    }
+
+    fn is_empty(&self, point: &Point) -> bool {
+        (self.red | self.blue) & self.bit(point) == 0
+    }
+
+    /// Every move the piece on the square can make, ignoring the rest of its side. Captures are
+    /// mandatory across the whole side, so use [`get_all_moves`](Board::get_all_moves) to find
+    /// out what's actually legal. Panics if the square is empty.
+    pub fn valid_moves(&self, acting_piece_cords: &Point) -> Result<Vec<Action>> {
+        let piece = self.piece_at(acting_piece_cords).unwrap();
+
+        // Jumps take priority, since a piece that can keep jumping has to
+        let actions = self.jumps(piece, acting_piece_cords);
+        if !actions.is_empty() {
+            return Ok(actions);
+        }
+
+        Ok(self.steps(piece, acting_piece_cords))
+    }
+
+    // Every non-capturing move for a piece
+    fn steps(&self, piece: Piece, from: &Point) -> Vec<Action> {
         let mut actions = Vec::new();
-        for x in 0..self.width {
-            for y in 0..self.height {
-                actions.push(Point { x, y });
+        for (dx, dy) in self.directions(&piece, false) {
+            // Flying kings can slide any distance, everything else moves a single step
+            let max_distance = if piece.crowned && self.rules.flying_kings { i16::MAX } else { 1 };
+
+            let mut distance = 1;
+            while distance <= max_distance {
+                // Move onto empty squares along the diagonal until something is in the way
+                let step = match self.offset(from, dx * distance, dy * distance) {
+                    Some(p) if self.is_empty(&p) => p,
+                    _ => break
+                };
+
+                actions.push(Action {
+                    from: from.clone(),
+                    path: vec![step],
+                    captures: Vec::new()
+                });
+                distance += 1;
             }
         }
 
-        let piece = self.state[acting_piece_cords.x as usize][acting_piece_cords.y as usize].piece.as_ref().unwrap();
+        actions
+    }
 
-        let actions_filtered: Vec<Point> = actions.into_iter().filter(|point| {
-            let x = point.x as usize;
-            let y = point.y as usize;
+    // Every maximal jump chain for a piece
+    fn jumps(&self, piece: Piece, from: &Point) -> Vec<Action> {
+        let mut actions = Vec::new();
+        self.find_jumps(piece, from, &mut Vec::new(), &mut Vec::new(), &mut actions);
+        actions
+    }
 
-            if acting_piece_cords.x.abs_diff(x as u8) != acting_piece_cords.y.abs_diff(y as u8) { // Non-diagonal spaces aren't valid moves
-                return false;
+    // Recursively finds every maximal jump chain for a piece, starting from the end of the given
+    // path (or from the piece itself if the path is empty)
+    fn find_jumps(&self, piece: Piece, from: &Point, path: &mut Vec<Point>, captures: &mut Vec<Point>, actions: &mut Vec<Action>) {
+        let current = path.last().unwrap_or(from).clone();
+        let flying = piece.crowned && self.rules.flying_kings;
+        let mut extended = false;
+
+        // The square the piece started on counts as empty since it's left it. Pieces that have
+        // already been jumped stay on the board until the chain is over, so they still block
+        let is_empty = |point: &Point| {
+            self.is_empty(point) || point == from
+        };
+
+        for (dx, dy) in self.directions(&piece, true) {
+            // Find the first piece along the diagonal. Only flying kings can look past the
+            // adjacent square
+            let mut distance = 1;
+            let jumped = loop {
+                match self.offset(&current, dx * distance, dy * distance) {
+                    Some(p) if is_empty(&p) && flying => distance += 1,
+                    other => break other
+                }
+            };
+            let jumped = match jumped {
+                Some(p) => p,
+                None => continue
+            };
+
+            // Can only jump an enemy piece once per chain
+            if !piece.side.piece_is_hostile(&self.piece_at(&jumped)) || captures.contains(&jumped) {
+                continue;
             }
 
-            if !piece.crowned { // Don't let pieces go backwards, but ignore if crowned
-                if piece.side == Side::Red { 
-                    if acting_piece_cords.y > y as u8 { 
-                        return false;
-                    }
+            // Flying kings can land on any empty square past the jumped piece, everything else
+            // lands right behind it
+            let mut landings = Vec::new();
+            let mut distance = distance + 1;
+            while let Some(landing) = self.offset(&current, dx * distance, dy * distance) {
+                if !is_empty(&landing) {
+                    break;
                 }
-                else if piece.side == Side::Blue { // Which direction is "backwards" depends on the side
-                    if acting_piece_cords.y < y as u8 {
-                        return false;
-                    }
+                landings.push(landing);
+                if !flying {
+                    break;
                 }
-            }
-        
-            if self.state[x][y].piece.is_some() { // Spaces with pieces aren't valid moves
-                return false;
+                distance += 1;
             }
 
-            return true;
-        }).collect();
+            // Work out every chain through each landing first, since a flying king has to land
+            // somewhere it can keep capturing from if there is anywhere
+            let mut chains = Vec::new();
+            for landing in landings {
+                extended = true;
+                path.push(landing.clone());
+                captures.push(jumped.clone());
 
-        Ok(actions_filtered)
-    }
+                let mut landing_actions = Vec::new();
+                let promoted = !piece.crowned && self.is_crowning_row(piece.side, landing.y);
+                match self.rules.mid_chain_promotion {
+                    // A man reaching the far row gets crowned, which ends its turn
+                    MidChainPromotion::EndsTurn if promoted => landing_actions.push(Action {
+                        from: from.clone(),
+                        path: path.clone(),
+                        captures: captures.clone()
+                    }),
+                    // A man reaching the far row gets crowned and keeps jumping as a king
+                    MidChainPromotion::Crowns if promoted => {
+                        self.find_jumps(Piece { side: piece.side, crowned: true }, from, path, captures, &mut landing_actions);
+                    },
+                    _ => self.find_jumps(piece, from, path, captures, &mut landing_actions)
+                }
+
+                let continues = landing_actions.iter().any(|action| action.captures.len() > captures.len());
+                chains.push((continues, landing_actions));
+
+                path.pop();
+                captures.pop();
+            }
 
-    pub fn get_all_moves(&self, side: Side) -> HashMap<Point, Vec<Point>> {
-        let mut all_moves = HashMap::new();
-        for x in 0..self.width {
-            for y in 0..self.height {
-                let piece = self.state[x as usize][y as usize].piece;
-                if side.piece_is_friendly(&piece) {
-                    let moves = self.valid_moves(&Point {x, y}).unwrap();
-                    if moves.len() != 0 {
-                        all_moves.insert(Point { x, y }, moves);
-                    }
+            let any_continue = chains.iter().any(|(continues, _)| *continues);
+            for (continues, landing_actions) in chains {
+                if continues || !any_continue {
+                    actions.extend(landing_actions);
                 }
             }
         }
 
-        all_moves
+        // If we jumped at least once but can't jump any further, this chain is done
+        if !extended && !path.is_empty() {
+            actions.push(Action {
+                from: from.clone(),
+                path: path.clone(),
+                captures: captures.clone()
+            });
+        }
     }
 
-    pub async fn do_action(&mut self, action: &Action) {
-        let from_piece = self.state[action.from.x as usize][action.from.y as usize].piece.unwrap();
+    // The diagonal directions a piece is allowed to travel in
+    fn directions(&self, piece: &Piece, capturing: bool) -> Vec<(i16, i16)> {
+        // Which y direction is "forward" depends on the side
+        let forward: i16 = match piece.side {
+            Side::Red => 1,
+            Side::Blue => -1
+        };
+
+        // Don't let pieces go backwards, but ignore if crowned or if the rules let men capture
+        // backwards
+        let any_direction = piece.crowned || (capturing && self.rules.men_capture_backward);
 
-        // Crown the piece, if applicable
-        let crowned = if from_piece.crowned {
-            true // If it's already crowned, keep it that way
+        [(-1, -1), (1, -1), (-1, 1), (1, 1)].into_iter().filter(|(_, dy)| {
+            any_direction || *dy == forward
+        }).collect()
+    }
+
+    fn is_crowning_row(&self, side: Side, y: u8) -> bool {
+        match side {
+            Side::Red => y == self.height() - 1,
+            Side::Blue => y == 0
         }
-        else {
-            if (from_piece.side == Side::Red && action.to.y == self.height - 1) || (from_piece.side == Side::Blue && action.to.y == 0) {
-                true
-            }
-            else {
-                false
-            }
-        };
+    }
+
+    // Returns the point (dx, dy) away from the given point, or None if that would be off the board
+    fn offset(&self, point: &Point, dx: i16, dy: i16) -> Option<Point> {
+        let x = point.x as i16 + dx;
+        let y = point.y as i16 + dy;
+
+        if x < 0 || y < 0 || x >= self.width() as i16 || y >= self.height() as i16 {
+            return None;
+        }
+
+        Some(Point { x: x as u8, y: y as u8 })
+    }
+
+    /// Every legal move for the side, grouped by the square of the piece making it. Pieces that
+    /// can't move are left out, so an empty map means the side has lost.
+    pub fn get_all_moves(&self, side: Side) -> HashMap<Point, Vec<Action>> {
+        let pieces: Vec<(Point, Piece)> = self.pieces().filter(|(_, piece)| piece.side == side).collect();
+
+        // Captures are mandatory, so if any piece can jump, only jumps are allowed. Check for
+        // those first so we don't bother working out every step when we won't need them
+        let mut all_moves: HashMap<Point, Vec<Action>> = pieces.iter()
+            .map(|(point, piece)| (point.clone(), self.jumps(*piece, point)))
+            .filter(|(_, moves)| !moves.is_empty())
+            .collect();
+
+        if all_moves.is_empty() {
+            return pieces.iter()
+                .map(|(point, piece)| (point.clone(), self.steps(*piece, point)))
+                .filter(|(_, moves)| !moves.is_empty())
+                .collect();
+        }
+
+        // Some rules also require taking as many pieces as possible
+        if self.rules.majority_capture {
+            let most_captures = all_moves.values().flatten().map(|action| action.captures.len()).max().unwrap_or(0);
+            all_moves.values_mut().for_each(|moves| moves.retain(|action| action.captures.len() == most_captures));
+            all_moves.retain(|_, moves| !moves.is_empty());
+        }
+
+        all_moves
+    }
+
+    /// Whether the action gets an uncrowned piece crowned.
+    pub fn crowns(&self, action: &Action) -> bool {
+        let piece = self.piece_at(&action.from).unwrap();
+        if piece.crowned {
+            return false;
+        }
+
+        // Some rules crown a man that touches the far row at any point during a jump chain
+        self.is_crowning_row(piece.side, action.to().y) || (
+            self.rules.mid_chain_promotion == MidChainPromotion::Crowns
+                && action.path.iter().any(|point| self.is_crowning_row(piece.side, point.y))
+        )
+    }
+
+    /// Makes the move, crowning the piece if it gets there and taking everything it captured. The
+    /// move isn't checked, so it has to be legal.
+    pub fn do_action(&mut self, action: &Action) {
+        let from_piece = self.piece_at(&action.from).unwrap();
+
+        // Crown the piece, if applicable. If it's already crowned, keep it that way
+        let crowned = from_piece.crowned || self.crowns(action);
 
         // Actually move the piece
-        self.state[action.from.x as usize][action.from.y as usize].piece = None;
-        self.state[action.to.x as usize][action.to.y as usize].piece = Some(Piece { side: from_piece.side, crowned });
-
-        // Find out the bigger/smaller x and y from the source/destination for below
-        let bigger_x;
-        let smaller_x;
-        let bigger_y;
-        let smaller_y;
-        if action.from.x > action.to.x {
-            bigger_x = action.from.x;
-            smaller_x = action.to.x;
+        self.set_piece(&action.from, None);
+        self.set_piece(action.to(), Some(Piece { side: from_piece.side, crowned }));
+
+        // Remove every piece that was jumped over along the way
+        for captured in &action.captures {
+            self.set_piece(captured, None);
         }
-        else {
-            bigger_x = action.to.x;
-            smaller_x = action.from.x;
+    }
+}
+
+// Iterates over the indices of the set bits, lowest first
+struct BitIter(u128);
+
+impl Iterator for BitIter {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        if self.0 == 0 {
+            return None;
         }
-        if action.from.y > action.to.y {
-            bigger_y = action.from.y;
-            smaller_y = action.to.y;
-        }   
-        else {
-            bigger_y = action.to.y;
-            smaller_y = action.from.y;
+
+        let index = self.0.trailing_zeros();
+        self.0 &= self.0 - 1; // Clear the lowest set bit
+        Some(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(rules: Ruleset, pieces: &[((u8, u8), Side, bool)]) -> Board {
+        let mut board = Board::empty(rules).unwrap();
+        for (point, side, crowned) in pieces {
+            board.set_piece(&(*point).into(), Some(Piece { side: *side, crowned: *crowned }));
         }
+        board
+    }
 
-        // Remove enemy pieces between source and destination
-        for x in smaller_x..bigger_x {
-            for y in smaller_y..bigger_y {
-                if action.from.x.abs_diff(x as u8) == action.from.y.abs_diff(y as u8) {
-                    if from_piece.side.piece_is_hostile(&self.state[x as usize][y as usize].piece) {
-                        self.state[x as usize][y as usize].piece = None;
-                    }
-                }
-            }
+    // Every move for the side as (destinations, captures), sorted so they're easy to compare
+    fn moves(board: &Board, side: Side) -> Vec<(Vec<(u8, u8)>, usize)> {
+        let mut moves: Vec<(Vec<(u8, u8)>, usize)> = board.get_all_moves(side).into_values().flatten()
+            .map(|action| (action.path.iter().map(|point| (point.x, point.y)).collect(), action.captures.len()))
+            .collect();
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn flying_king_lands_where_it_can_keep_capturing() {
+        let board = board(Ruleset::russian(), &[
+            ((0, 0), Side::Red, true),
+            ((2, 2), Side::Blue, false),
+            ((5, 3), Side::Blue, false),
+            ((7, 7), Side::Blue, false)
+        ]);
+
+        assert_eq!(moves(&board, Side::Red), vec![
+            (vec![(4, 4), (6, 2)], 2),
+            (vec![(4, 4), (7, 1)], 2)
+        ]);
+    }
+
+    #[test]
+    fn flying_king_can_stop_anywhere_when_no_landing_continues() {
+        let board = board(Ruleset::russian(), &[
+            ((0, 0), Side::Red, true),
+            ((2, 2), Side::Blue, false)
+        ]);
+
+        assert_eq!(moves(&board, Side::Red), vec![
+            (vec![(3, 3)], 1),
+            (vec![(4, 4)], 1),
+            (vec![(5, 5)], 1),
+            (vec![(6, 6)], 1),
+            (vec![(7, 7)], 1)
+        ]);
+    }
+
+    #[test]
+    fn majority_capture_only_allows_the_longest_chains() {
+        let pieces = [
+            ((4, 4), Side::Red, false),
+            ((3, 5), Side::Blue, false),
+            ((3, 7), Side::Blue, false),
+            ((5, 5), Side::Blue, false)
+        ];
+
+        assert_eq!(moves(&board(Ruleset::international(), &pieces), Side::Red), vec![
+            (vec![(2, 6), (4, 8)], 2)
+        ]);
+
+        let rules = Ruleset { majority_capture: false, ..Ruleset::international() };
+        assert_eq!(moves(&board(rules, &pieces), Side::Red), vec![
+            (vec![(2, 6), (4, 8)], 2),
+            (vec![(6, 6)], 1)
+        ]);
+    }
+
+    // A red man one jump away from the far row, with another piece it could take from there
+    const PROMOTION_PIECES: [((u8, u8), Side, bool); 3] = [
+        ((1, 5), Side::Red, false),
+        ((2, 6), Side::Blue, false),
+        ((4, 6), Side::Blue, false)
+    ];
+
+    #[test]
+    fn promotion_ends_the_turn() {
+        let board = board(Ruleset::american(), &PROMOTION_PIECES);
+
+        assert_eq!(moves(&board, Side::Red), vec![(vec![(3, 7)], 1)]);
+        let action = &board.get_all_moves(Side::Red)[&Point { x: 1, y: 5 }][0];
+        assert!(board.crowns(action));
+    }
+
+    #[test]
+    fn promotion_crowns_and_keeps_jumping_as_a_king() {
+        let board = board(Ruleset::russian(), &PROMOTION_PIECES);
+
+        // Crowned on the far row, then flies on past the second piece
+        assert_eq!(moves(&board, Side::Red), vec![
+            (vec![(3, 7), (5, 5)], 2),
+            (vec![(3, 7), (6, 4)], 2),
+            (vec![(3, 7), (7, 3)], 2)
+        ]);
+        for action in board.get_all_moves(Side::Red).into_values().flatten() {
+            assert!(board.crowns(&action));
         }
     }
+
+    #[test]
+    fn promotion_passes_through_as_a_man() {
+        let board = board(Ruleset::pool(), &PROMOTION_PIECES);
+
+        // Still a man, so it only lands right behind the second piece and doesn't get crowned
+        assert_eq!(moves(&board, Side::Red), vec![(vec![(3, 7), (5, 5)], 2)]);
+        let action = &board.get_all_moves(Side::Red)[&Point { x: 1, y: 5 }][0];
+        assert!(!board.crowns(action));
+    }
 }
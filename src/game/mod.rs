@@ -1,102 +1,282 @@
-mod terminal;
-mod board;
+//! Running a game between two players.
 
-use std::io::Stdout;
-use crossterm::{Result, ErrorKind};
-use crate::{actor::{ActorType, Actor, ActionResult}, side::Side};
-use self::{terminal::TerminalWrapper, board::Board};
+pub mod terminal;
+pub mod board;
+pub mod position;
 
+use std::{io::Stdout, sync::Arc};
+use std::time::{Duration, Instant};
+use std::io::{Result, Error};
+use tokio::{sync::Mutex, time::sleep};
+use crate::{actor::{Player, ActionResult, Action}, side::Side, rules::{Ruleset, DrawRules}};
+use self::{terminal::{SharedTerminal, TerminalWrapper}, board::Board, position::Position};
+
+/// Which of the [`DrawRules`] ended the game.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DrawReason {
+    /// The same position came up too many times.
+    Repetition,
+    /// Too long without a capture or a man moving.
+    NoProgress,
+    /// The game went on for the most moves allowed.
+    MoveLimit
+}
+
+/// How a game ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Termination {
+    /// The loser has no pieces left.
+    Elimination,
+    /// The loser has pieces left, but none of them can move.
+    Blockade,
+    /// The loser gave up.
+    Resignation,
+    /// The loser took longer than the move time limit.
+    Timeout,
+    /// Nobody won.
+    Draw(DrawReason)
+}
+
+/// How a finished game went.
 #[derive(Debug)]
 pub struct GameResult {
+    /// How many moves were played in this game, not counting any made before it started.
     pub moves: usize,
-    pub winner: Side
+    /// None if the game was a draw.
+    pub winner: Option<Side>,
+    /// How the game ended.
+    pub termination: Termination
+}
+
+impl std::fmt::Display for GameResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.winner, self.termination) {
+            (_, Termination::Draw(reason)) => write!(f, "Draw by {:?} after {} moves", reason, self.moves),
+            (Some(winner), termination) => write!(f, "{:?} won by {:?} after {} moves", winner, termination, self.moves),
+            (None, termination) => write!(f, "No winner ({:?}) after {} moves", termination, self.moves)
+        }
+    }
 }
 
+/// A game in progress. Set any of the public fields before calling [`play`](Game::play) to
+/// change how it's played.
 pub struct Game {
-    pub board: Board,
-    pub terminal_wrapper: Option<TerminalWrapper>,
+    /// The current position, which is the end of the game once it's over.
+    pub position: Position,
+    /// When the game counts as a draw.
+    pub draw_rules: DrawRules,
+    /// How long each player gets to decide on a move.
+    pub move_time_limit: Option<Duration>,
+    /// Makes the players' random choices repeatable.
+    pub seed: Option<u64>,
+    /// Where the game is drawn, if anywhere. Human players that play on the terminal need one.
+    pub terminal_wrapper: Option<SharedTerminal>,
+    redo_stack: Vec<Action> // Moves that were undone, most recently undone last
 }
 
 impl Game {
-    pub fn new(width: u8, height: u8, terminal: Option<Stdout>) -> Result<Game> {
+    /// A new game from the starting position, drawn on the terminal if one is given.
+    pub fn new(rules: Ruleset, terminal: Option<Stdout>) -> Result<Game> {
+        let board = Board::new(rules)?;
+        Game::from_position(Position::new(board, rules.first_mover), terminal)
+    }
+
+    /// A game that carries on from the position, drawn on the terminal if one is given.
+    pub fn from_position(position: Position, terminal: Option<Stdout>) -> Result<Game> {
         let terminal_wrapper = match terminal {
-            Some(t) => Some(TerminalWrapper::new(t)?),
+            Some(t) => Some(Arc::new(Mutex::new(TerminalWrapper::new(t)?))),
             None => None
         };
 
-        let board = Board::new(width, height)?;
-
         Ok(Game {
-            board,
-            terminal_wrapper
+            position,
+            draw_rules: DrawRules::default(),
+            move_time_limit: None,
+            seed: None,
+            terminal_wrapper,
+            redo_stack: Vec::new()
         })
     }
 
-    pub async fn play(&mut self, red_actor_type: ActorType, blue_actor_type: ActorType) -> Result<Option<GameResult>> {
-        if self.terminal_wrapper.is_none() && (red_actor_type == ActorType::Human || blue_actor_type == ActorType::Human) {
-            return Err(ErrorKind::new(std::io::ErrorKind::Unsupported, "Cannot have human actor on virtual board"));
-        }
+    /// Plays until the game ends, or until the players quit if there's a terminal. Returns None if
+    /// they quit before the end. Errors if a player can't play this game, or if a player makes an
+    /// illegal move.
+    pub async fn play(&mut self, mut red: Box<dyn Player>, mut blue: Box<dyn Player>) -> Result<Option<GameResult>> {
+        red.game_started(Side::Red, self).await?;
+        blue.game_started(Side::Blue, self).await?;
 
-        let red_actor = Actor {
-            actor_type: red_actor_type,
-            side: Side::Red
-        };
-        let blue_actor = Actor {
-            actor_type: blue_actor_type,
-            side: Side::Blue
-        };
+        let humans = (red.is_human(), blue.is_human());
+        let is_human = |side| if side == Side::Red { humans.0 } else { humans.1 };
 
-        let mut winner = None;
-        let mut moves = 0;
+        let mut result = None;
+        let start_ply = self.position.ply();
 
-        // TODO: Cancel out if too many AI-on-AI iterations without kill
         loop {
-            // Can ignore exit request if no terminal
-            if self.terminal_wrapper.is_some() {
-                if self.terminal_wrapper.as_ref().unwrap().exit_requested.load(std::sync::atomic::Ordering::Relaxed) {
-                    return Ok(winner);
+            if let Some(game_result) = &result {
+                red.game_ended(game_result).await;
+                blue.game_ended(game_result).await;
+                return Ok(result);
+            }
+
+            // Can ignore exit request if no terminal. Anything pressed since the last turn is
+            // thrown away, but asking to quit gets asked about now, even between two bots' moves
+            if let Some(terminal_wrapper) = &self.terminal_wrapper {
+                let mut terminal_wrapper = terminal_wrapper.lock().await;
+                terminal_wrapper.discard_input(self.position.board()).await?;
+                if terminal_wrapper.exit_requested.load(std::sync::atomic::Ordering::Relaxed) {
+                    return Ok(None);
                 }
             }
-            else if self.terminal_wrapper.is_none() && winner.is_some() {
-                return Ok(winner);
+
+            let side = self.position.side_to_move();
+            let (player, opponent) = match side {
+                Side::Red => (&mut red, &mut blue),
+                Side::Blue => (&mut blue, &mut red)
+            };
+
+            // A side that can't move loses
+            let legal_moves = self.position.legal_moves();
+            if legal_moves.is_empty() {
+                let termination = if self.position.board().count_pieces(side) == 0 { Termination::Elimination } else { Termination::Blockade };
+                result = Some(GameResult { moves: self.position.ply() - start_ply, winner: Some(side.opponent()), termination });
+                continue;
             }
-            
-            if winner.is_none() {
-                match red_actor.act(self).await {
-                    ActionResult::NoPiecesLeft => {
-                        if self.terminal_wrapper.is_some() {
-                            println!("Blue won!");
-                        }
-                        winner = Some(GameResult { moves, winner: Side::Blue });
-                        continue;
-                    },
-                    ActionResult::TookAction(action) => {
-                        self.board.do_action(&action).await;
-                        if self.terminal_wrapper.is_some() {
-                            self.terminal_wrapper.as_mut().unwrap().draw(&self.board).await?;
-                        }
-                        moves += 1;
+
+            // Slow the bots down a little if someone's watching
+            if !player.is_human() && self.terminal_wrapper.is_some() {
+                sleep(Duration::from_millis(100)).await;
+            }
+
+            let choice = player.choose_action(&self.position, &legal_moves);
+            let action_result = match self.move_time_limit {
+                Some(limit) => {
+                    // Players that don't yield while thinking can't be cut off, so also check how
+                    // long they took once they're done
+                    let start = Instant::now();
+                    match tokio::time::timeout(limit, choice).await {
+                        Ok(action_result) if start.elapsed() <= limit => Some(action_result),
+                        _ => None
                     }
-                }
+                },
+                None => Some(choice.await)
+            };
+
+            let termination = match action_result {
+                Some(ActionResult::TookAction(action)) => {
+                    if !legal_moves.values().flatten().any(|legal| *legal == action) {
+                        return Err(Error::new(std::io::ErrorKind::InvalidData, format!("{:?} tried to make an illegal move", side)));
+                    }
+
+                    self.redo_stack.clear(); // A new move means the undone ones can't be redone
+                    self.position.make_move(&action);
+                    opponent.opponent_moved(&action, &self.position).await;
+                    self.redraw().await?;
 
-                match blue_actor.act(self).await {
-                    ActionResult::NoPiecesLeft => {
-                        if self.terminal_wrapper.is_some() {
-                            println!("Red won!");
+                    let moves = self.position.ply() - start_ply;
+                    if let Some(reason) = self.draw_reason(moves) {
+                        result = Some(GameResult { moves, winner: None, termination: Termination::Draw(reason) });
+                    }
+                    continue;
+                },
+                Some(ActionResult::Undo) => {
+                    // Take back at least one move, then keep going until it's a human's turn
+                    // again so the AI gets to think again rather than being undone one move at a
+                    // time. Never go back past where this game started
+                    while self.position.ply() > start_ply {
+                        self.redo_stack.push(self.position.unmake_move().unwrap());
+                        if is_human(self.position.side_to_move()) {
+                            break;
                         }
-                        winner = Some(GameResult { moves, winner: Side::Red });
-                        continue;
-                    },
-                    ActionResult::TookAction(action) => {
-                        self.board.do_action(&action).await;
-                        if self.terminal_wrapper.is_some() {
-                            self.terminal_wrapper.as_mut().unwrap().draw(&self.board).await?;
+                    }
+                    self.redraw().await?;
+                    continue;
+                },
+                Some(ActionResult::Redo) => {
+                    // Replay undone moves until it's a human's turn again, or there's nothing left
+                    // to redo, in which case the AI will think for itself
+                    while let Some(action) = self.redo_stack.pop() {
+                        self.position.make_move(&action);
+                        if is_human(self.position.side_to_move()) {
+                            break;
                         }
-                        moves += 1;
                     }
-                }
+                    self.redraw().await?;
+                    continue;
+                },
+                Some(ActionResult::Quit) => return Ok(None),
+                Some(ActionResult::Resigned) => Termination::Resignation,
+                None => Termination::Timeout
+            };
 
-            }
+            // Anything other than taking an action means this player lost
+            result = Some(GameResult { moves: self.position.ply() - start_ply, winner: Some(side.opponent()), termination });
+        }
+    }
+
+    async fn redraw(&mut self) -> Result<()> {
+        if let Some(terminal_wrapper) = &self.terminal_wrapper {
+            terminal_wrapper.lock().await.draw(self.position.board()).await?;
+        }
+        Ok(())
+    }
+
+    fn draw_reason(&self, moves: usize) -> Option<DrawReason> {
+        if self.draw_rules.repetitions.is_some_and(|limit| self.position.repetitions() >= limit) {
+            Some(DrawReason::Repetition)
+        }
+        else if self.draw_rules.no_progress_limit.is_some_and(|limit| self.position.moves_without_progress() >= limit) {
+            Some(DrawReason::NoProgress)
+        }
+        else if self.draw_rules.move_limit.is_some_and(|limit| moves >= limit) {
+            Some(DrawReason::MoveLimit)
+        }
+        else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use async_trait::async_trait;
+    use crate::{point::Point, actor::{ActorType, human::HumanPlayer}};
+
+    // Stands in for someone playing from somewhere other than the terminal, who gives up straight
+    // away
+    struct RemoteHuman;
+
+    #[async_trait]
+    impl Player for RemoteHuman {
+        async fn choose_action(&mut self, _position: &Position, _legal_moves: &HashMap<Point, Vec<Action>>) -> ActionResult {
+            ActionResult::Resigned
+        }
+
+        fn is_human(&self) -> bool {
+            true
         }
     }
+
+    #[test]
+    fn the_rules_say_who_moves_first() {
+        for (rules, side) in [(Ruleset::american(), Side::Red), (Ruleset::international(), Side::Blue), (Ruleset::russian(), Side::Blue), (Ruleset::brazilian(), Side::Blue), (Ruleset::pool(), Side::Red)] {
+            assert_eq!(Game::new(rules, None).unwrap().position.side_to_move(), side, "{:?}", rules.variant);
+        }
+    }
+
+    #[tokio::test]
+    async fn humans_can_play_without_a_terminal() {
+        let mut game = Game::new(Ruleset::american(), None).unwrap();
+        let result = game.play(Box::new(RemoteHuman), ActorType::Random.player()).await.unwrap().unwrap();
+        assert_eq!(result.termination, Termination::Resignation);
+        assert_eq!(result.winner, Some(Side::Blue));
+    }
+
+    #[tokio::test]
+    async fn terminal_players_need_a_terminal() {
+        let mut game = Game::new(Ruleset::american(), None).unwrap();
+        let error = game.play(ActorType::Random.player(), Box::new(HumanPlayer::new())).await.err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::Unsupported);
+    }
 }
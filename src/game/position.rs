@@ -0,0 +1,239 @@
+//! A board in the middle of a game.
+
+use std::collections::HashMap;
+use crate::{piece::Piece, side::Side, point::Point, actor::Action};
+use super::board::Board;
+
+// Everything needed to put the board back the way it was before a move
+#[derive(Clone)]
+struct HistoryEntry {
+    action: Action,
+    piece: Piece, // The moving piece, before it was possibly crowned
+    captured: Vec<(Point, Piece)>,
+    moves_without_progress: usize,
+    key: (u128, u128, u128, Side) // The position after the move, for spotting repetitions
+}
+
+/// The board along with whose turn it is and how the game got here. Moves can be made and taken
+/// back, which is what the searches use to look ahead.
+#[derive(Clone)]
+pub struct Position {
+    board: Board,
+    side_to_move: Side,
+    ply: usize,
+    moves_without_progress: usize,
+    history: Vec<HistoryEntry>,
+    start_key: (u128, u128, u128, Side)
+}
+
+impl Position {
+    /// A position with no history, so it can't be taken back any further.
+    pub fn new(board: Board, side_to_move: Side) -> Position {
+        let (red, blue, kings) = board.position_key();
+        Position {
+            board,
+            side_to_move,
+            ply: 0,
+            moves_without_progress: 0,
+            history: Vec::new(),
+            start_key: (red, blue, kings, side_to_move)
+        }
+    }
+
+    /// Where the pieces are now. Moves are made through the position so it can keep track of them.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Whose turn it is.
+    pub fn side_to_move(&self) -> Side {
+        self.side_to_move
+    }
+
+    /// How many moves have been made, counting each side's turn separately.
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    /// Moves since the last capture or man moving.
+    pub fn moves_without_progress(&self) -> usize {
+        self.moves_without_progress
+    }
+
+    /// Every legal move for the side to move, grouped by the square of the piece making it.
+    pub fn legal_moves(&self) -> HashMap<Point, Vec<Action>> {
+        self.board.get_all_moves(self.side_to_move)
+    }
+
+    /// Every action made so far, oldest first.
+    pub fn history(&self) -> impl DoubleEndedIterator<Item = &Action> {
+        self.history.iter().map(|entry| &entry.action)
+    }
+
+    /// Makes the move and passes the turn over. The move isn't checked, so it has to be legal.
+    pub fn make_move(&mut self, action: &Action) {
+        let piece = self.board.piece_at(&action.from).unwrap();
+        let captured = action.captures.iter().map(|point| (point.clone(), self.board.piece_at(point).unwrap())).collect();
+        let moves_without_progress = self.moves_without_progress;
+
+        // Captures and men moving can't be undone, so they count as progress
+        if action.is_capture() || !piece.crowned {
+            self.moves_without_progress = 0;
+        }
+        else {
+            self.moves_without_progress += 1;
+        }
+
+        self.board.do_action(action);
+        self.side_to_move = self.side_to_move.opponent();
+        self.ply += 1;
+
+        let (red, blue, kings) = self.board.position_key();
+        self.history.push(HistoryEntry {
+            action: action.clone(),
+            piece,
+            captured,
+            moves_without_progress,
+            key: (red, blue, kings, self.side_to_move)
+        });
+    }
+
+    /// Takes back the last move, returning it. Returns None if there's nothing to take back.
+    pub fn unmake_move(&mut self) -> Option<Action> {
+        let entry = self.history.pop()?;
+
+        // Clear the destination first, since a jump chain can end where it started
+        self.board.set_piece(entry.action.to(), None);
+        self.board.set_piece(&entry.action.from, Some(entry.piece));
+        for (point, piece) in entry.captured {
+            self.board.set_piece(&point, Some(piece));
+        }
+
+        self.moves_without_progress = entry.moves_without_progress;
+        self.side_to_move = self.side_to_move.opponent();
+        self.ply -= 1;
+
+        Some(entry.action)
+    }
+
+    /// How many times the current position has come up. Only looks back as far as the last
+    /// capture or man moving, since nothing before that can come up again.
+    pub fn repetitions(&self) -> usize {
+        let current = match self.history.last() {
+            Some(entry) => entry.key,
+            None => return 1
+        };
+
+        let earlier = self.history.iter().rev().skip(1).take(self.moves_without_progress).map(|entry| entry.key);
+        let start = if self.moves_without_progress >= self.history.len() { Some(self.start_key) } else { None };
+
+        1 + earlier.chain(start).filter(|key| *key == current).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Ruleset;
+
+    fn step(from: (u8, u8), to: (u8, u8)) -> Action {
+        Action { from: from.into(), path: vec![to.into()], captures: Vec::new() }
+    }
+
+    // A king each in opposite corners, plus a red man that can make progress
+    fn kings_and_a_man() -> Position {
+        let mut board = Board::empty(Ruleset::american()).unwrap();
+        board.set_piece(&(0, 0).into(), Some(Piece { side: Side::Red, crowned: true }));
+        board.set_piece(&(4, 2).into(), Some(Piece { side: Side::Red, crowned: false }));
+        board.set_piece(&(7, 7).into(), Some(Piece { side: Side::Blue, crowned: true }));
+        Position::new(board, Side::Red)
+    }
+
+    // Both kings step out of their corners and back again
+    fn shuffle(position: &mut Position) {
+        position.make_move(&step((0, 0), (1, 1)));
+        position.make_move(&step((7, 7), (6, 6)));
+        position.make_move(&step((1, 1), (0, 0)));
+        position.make_move(&step((6, 6), (7, 7)));
+    }
+
+    #[test]
+    fn king_shuffles_repeat_the_start() {
+        let mut position = kings_and_a_man();
+        assert_eq!(position.repetitions(), 1);
+
+        position.make_move(&step((0, 0), (1, 1)));
+        assert_eq!(position.repetitions(), 1);
+        position.make_move(&step((7, 7), (6, 6)));
+        position.make_move(&step((1, 1), (0, 0)));
+        position.make_move(&step((6, 6), (7, 7)));
+        assert_eq!(position.repetitions(), 2); // The start, which isn't in the history, counts too
+
+        shuffle(&mut position);
+        assert_eq!(position.repetitions(), 3);
+        position.unmake_move();
+        assert_eq!(position.repetitions(), 2); // After Red's king went back the second time
+    }
+
+    #[test]
+    fn repetitions_only_count_since_the_last_progress() {
+        let mut position = kings_and_a_man();
+        shuffle(&mut position);
+
+        // The man moving means nothing before it can come up again, so counting starts over
+        position.make_move(&step((4, 2), (5, 3)));
+        position.make_move(&step((7, 7), (6, 6)));
+        position.make_move(&step((0, 0), (1, 1)));
+        position.make_move(&step((6, 6), (7, 7)));
+        assert_eq!(position.repetitions(), 1);
+        position.make_move(&step((1, 1), (0, 0)));
+        assert_eq!(position.repetitions(), 2);
+    }
+
+    #[test]
+    fn progress_is_captures_and_men_moving() {
+        let mut position = kings_and_a_man();
+        position.make_move(&step((0, 0), (1, 1)));
+        position.make_move(&step((7, 7), (6, 6)));
+        assert_eq!(position.moves_without_progress(), 2);
+
+        position.make_move(&step((4, 2), (5, 3)));
+        assert_eq!(position.moves_without_progress(), 0);
+        position.make_move(&step((6, 6), (7, 5)));
+        position.make_move(&step((1, 1), (2, 2)));
+        position.make_move(&step((7, 5), (6, 4)));
+        position.make_move(&step((2, 2), (1, 1)));
+        assert_eq!(position.moves_without_progress(), 4);
+
+        // A king taking something counts as progress too
+        position.make_move(&Action { from: (6, 4).into(), path: vec![(4, 2).into()], captures: vec![(5, 3).into()] });
+        assert_eq!(position.moves_without_progress(), 0);
+
+        // Taking moves back puts the count back as well
+        position.unmake_move();
+        assert_eq!(position.moves_without_progress(), 4);
+    }
+
+    #[test]
+    fn unmaking_a_crowning_capture_puts_everything_back() {
+        let mut board = Board::empty(Ruleset::american()).unwrap();
+        board.set_piece(&(1, 5).into(), Some(Piece { side: Side::Red, crowned: false }));
+        board.set_piece(&(2, 6).into(), Some(Piece { side: Side::Blue, crowned: true }));
+        board.set_piece(&(5, 7).into(), Some(Piece { side: Side::Blue, crowned: false }));
+        let mut position = Position::new(board, Side::Red);
+        let before = position.clone();
+
+        let capture = Action { from: (1, 5).into(), path: vec![(3, 7).into()], captures: vec![(2, 6).into()] };
+        position.make_move(&capture);
+        assert_eq!(position.board().piece_at(&(3, 7).into()), Some(Piece { side: Side::Red, crowned: true }));
+        assert_eq!(position.board().piece_at(&(2, 6).into()), None);
+
+        assert_eq!(position.unmake_move(), Some(capture));
+        assert_eq!(position.board().position_key(), before.board().position_key());
+        assert_eq!(position.board().piece_at(&(1, 5).into()), Some(Piece { side: Side::Red, crowned: false }));
+        assert_eq!(position.board().piece_at(&(2, 6).into()), Some(Piece { side: Side::Blue, crowned: true }));
+        assert_eq!(position.side_to_move(), Side::Red);
+        assert_eq!(position.ply(), 0);
+        assert_eq!(position.unmake_move(), None);
+    }
+}
@@ -1,10 +1,13 @@
-use std::{sync::{Arc, atomic::AtomicBool}, io::{Stdout, Write}};
-use tokio::{sync::mpsc::{channel, Receiver, Sender}, task::JoinHandle};
+//! Drawing the board in a terminal and reading the player's mouse and keyboard.
+
+use std::{sync::{Arc, atomic::{AtomicBool, Ordering}}, io::{Result, Error, Stdout, Write}, collections::HashSet, iter::once, time::Duration};
+use tokio::{sync::{Mutex, mpsc::{channel, Receiver, Sender}}, task::JoinHandle};
 use crate::point::Point;
-use crate::game::board::Board;
-use crate::Side;
+use crate::game::{board::Board, position::Position};
+use crate::actor::Action;
+use crate::notation::pdn::action_to_string;
+use crate::side::Side;
 use crossterm::{
-    Result,
     QueueableCommand,
     ExecutableCommand,
     style::{Color, SetForegroundColor},
@@ -12,13 +15,80 @@ use crossterm::{
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, Clear, SetSize, enable_raw_mode, disable_raw_mode, is_raw_mode_enabled},
     cursor::{MoveTo, Hide, Show},
     style::SetBackgroundColor,
-    event::{Event, KeyCode, MouseEventKind, EnableMouseCapture, DisableMouseCapture}, ErrorKind
+    event::{Event, KeyCode, MouseEvent, MouseEventKind, MouseButton, EnableMouseCapture, DisableMouseCapture}
 };
 
+/// Something the player did that the game needs to respond to.
+pub enum Input {
+    /// A square on the board was clicked.
+    Click(Point),
+    /// An arrow key or one of hjkl, as the x and y it moves by. Steps through replays when it's
+    /// left or right.
+    MoveCursor(i16, i16),
+    /// Enter or Space, for picking the square under the keyboard cursor.
+    Select,
+    /// Esc or a right click, for putting down the selected piece or clearing the typed move.
+    Cancel,
+    /// Backspace, which deletes the last typed character, or works like Cancel if nothing's been
+    /// typed.
+    Erase,
+    /// A digit, '-' or 'x', for typing moves.
+    Type(char),
+    /// Tab, for completing a typed move.
+    Complete,
+    /// A whole typed move, once Enter is pressed.
+    Typed(String),
+    /// Give up the game.
+    Resign,
+    /// Take back a move.
+    Undo,
+    /// Put back a move that was taken back.
+    Redo,
+    /// The player wants to quit. They get asked to make sure before the game stops.
+    Quit,
+    /// Jump to the start of a replay.
+    First,
+    /// Jump to the end of a replay.
+    Last
+}
+
+/// Everything drawn on top of the board that isn't part of the game itself.
+#[derive(Default)]
+pub struct Overlay {
+    /// Squares to draw in a different colour, like the pieces that can move.
+    pub highlighted: HashSet<Point>,
+    /// The square the keyboard cursor is on. Hidden until the keyboard is used.
+    pub cursor: Option<Point>,
+    /// Printed under the board, such as what the player should do next.
+    pub message: String,
+    /// Where moves can be typed, under the message. Hidden if None.
+    pub prompt: Option<Prompt>
+}
+
+/// A move being typed.
+#[derive(Default)]
+pub struct Prompt {
+    /// What's been typed so far.
+    pub text: String,
+    /// Shown under the prompt, like the moves it could be completed to or why a move wasn't
+    /// accepted. Cleared as soon as the player types anything.
+    pub hint: String
+}
+
+/// A terminal shared between the game, which draws on it, and any human players, who read their
+/// moves from it.
+pub type SharedTerminal = Arc<Mutex<TerminalWrapper>>;
+
+/// Takes over the terminal while it's alive, and puts it back to normal when it's dropped. Only
+/// one can exist at a time.
 pub struct TerminalWrapper {
+    /// Where everything gets drawn.
     pub terminal: Stdout,
+    /// What gets drawn on top of the board next time it's drawn.
+    pub overlay: Overlay,
+    /// Set once the player has confirmed they want to quit, after which there's no more input.
     pub exit_requested: Arc<AtomicBool>,
-    click_events_rx: Receiver<Point>,
+    input_events_rx: Receiver<Input>,
     event_loop_handle: JoinHandle<()>
 }
 
@@ -32,6 +102,8 @@ impl Drop for TerminalWrapper {
 }
 
 impl TerminalWrapper {
+    /// Switches the terminal over to drawing the game and starts listening for input. Panics if
+    /// there's already a wrapper using the terminal.
     pub fn new(mut terminal: Stdout) -> Result<TerminalWrapper> {
         let (tx, rx) = channel(8);
         let exit_requested = Arc::new(AtomicBool::new(false));
@@ -48,7 +120,8 @@ impl TerminalWrapper {
 
         let wrapper = TerminalWrapper {
             terminal,
-            click_events_rx: rx,
+            overlay: Overlay::default(),
+            input_events_rx: rx,
             exit_requested,
             event_loop_handle
         };
@@ -56,16 +129,20 @@ impl TerminalWrapper {
         Ok(wrapper)
     }
 
+    /// Clears the screen and draws the board, along with the overlay.
     pub async fn draw(&mut self, board: &Board) -> Result<()> {
         self.terminal.queue(Clear(crossterm::terminal::ClearType::All))?;
-        self.terminal.queue(SetSize(board.width as u16, board.height as u16))?;
+        self.terminal.queue(SetSize(board.width() as u16, board.height() as u16))?;
 
-        for x in 0..board.width {
-            for y in 0..board.height {
+        for x in 0..board.width() {
+            for y in 0..board.height() {
                 self.terminal.queue(MoveTo(x as u16 * 2, y as u16))?;
 
                 let bg_color: Color;
-                if board.state[x as usize][y as usize].highlighted {
+                if self.overlay.cursor == Some(Point { x, y }) {
+                    bg_color = Color::DarkCyan;
+                }
+                else if self.overlay.highlighted.contains(&Point { x, y }) {
                     bg_color = Color::DarkYellow;
                 }
                 else if (x % 2) == (y % 2) {
@@ -78,7 +155,7 @@ impl TerminalWrapper {
                 self.terminal.queue(SetBackgroundColor(bg_color))?;
 
                 let mut print_str: String;
-                match board.state[x as usize][y as usize].piece {
+                match board.piece_at(&Point { x, y }) {
                     None => print_str = "  ".to_string(),
                     Some(ref p) => {
                         print_str = "⦿".to_string();
@@ -103,47 +180,282 @@ impl TerminalWrapper {
         self.terminal.queue(SetBackgroundColor(Color::Reset))?;
         self.terminal.queue(SetForegroundColor(Color::Reset))?;
         self.terminal.queue(Print("\n\r"))?;
+        for line in self.overlay.message.lines() {
+            self.terminal.queue(Print(format!("{}\n\r", line)))?;
+        }
+        if let Some(prompt) = &self.overlay.prompt {
+            self.terminal.queue(Print(format!("Move: {}_\n\r{}\n\r", prompt.text, prompt.hint)))?; // The terminal's own cursor is hidden
+        }
         self.terminal.flush()?;
 
         Ok(())
     }
 
-    pub async fn next_click(&mut self, board: &Board) -> Result<Point> {
+    /// Steps through a finished game one move at a time, starting after the given number of moves,
+    /// until the viewer quits. The last move is highlighted along with everything it captured.
+    pub async fn replay(&mut self, start: &Position, actions: &[Action], mut shown: usize, summary: &str) -> Result<()> {
+        let mut position = start.clone();
+        for action in &actions[..shown] {
+            position.make_move(action);
+        }
+
+        self.overlay.cursor = None;
+        loop {
+            let last_action = shown.checked_sub(1).map(|index| &actions[index]);
+            self.overlay.highlighted = match last_action {
+                Some(action) => once(&action.from).chain(&action.path).chain(&action.captures).cloned().collect(),
+                None => HashSet::new()
+            };
+
+            let status = match last_action {
+                Some(action) => format!("Move {}/{}: {}", shown, actions.len(), action_to_string(action, position.board().width())),
+                None => format!("Start of game, {} moves", actions.len())
+            };
+            self.overlay.message = format!("{}\n{}\nLeft/right to step through moves, Home/End to jump to the start/end, q to exit", status, summary);
+            self.draw(position.board()).await?;
+
+            // The input channel closes when the viewer quits
+            let input = match self.next_input(position.board()).await {
+                Ok(input) => input,
+                Err(_) if self.exit_requested.load(Ordering::Relaxed) => break,
+                Err(error) => return Err(error)
+            };
+
+            match input {
+                Input::MoveCursor(1, 0) if shown < actions.len() => {
+                    position.make_move(&actions[shown]);
+                    shown += 1;
+                },
+                Input::MoveCursor(-1, 0) if shown > 0 => {
+                    position.unmake_move();
+                    shown -= 1;
+                },
+                Input::First => while shown > 0 {
+                    position.unmake_move();
+                    shown -= 1;
+                },
+                Input::Last => while shown < actions.len() {
+                    position.make_move(&actions[shown]);
+                    shown += 1;
+                },
+                _ => ()
+            }
+        }
+
+        self.overlay.highlighted.clear();
+        self.overlay.message.clear();
+        Ok(())
+    }
+
+    /// Waits for the player to pick a square, either by clicking it or by moving the keyboard
+    /// cursor onto it and pressing Enter or Space, or to type a whole move into the prompt. The
+    /// cursor and prompt are updated and redrawn here, with Tab completing the typed move to one of
+    /// the given moves, and anything else the player does is passed on.
+    pub async fn next_choice(&mut self, board: &Board, moves: &[String]) -> Result<Input> {
+        loop {
+            let typing = self.overlay.prompt.as_ref().is_some_and(|prompt| !prompt.text.is_empty());
+            match self.next_input(board).await? {
+                Input::MoveCursor(dx, dy) => self.move_cursor(board, dx, dy).await?,
+                Input::Type(character) => if let Some(prompt) = &mut self.overlay.prompt {
+                    prompt.text.push(character);
+                    prompt.hint.clear();
+                    self.draw(board).await?;
+                },
+                Input::Complete => if let Some(prompt) = &mut self.overlay.prompt {
+                    complete(prompt, moves);
+                    self.draw(board).await?;
+                },
+                // Enter, Backspace and Esc work on the typed move while there is one
+                Input::Erase if typing => {
+                    let prompt = self.overlay.prompt.as_mut().unwrap();
+                    prompt.text.pop();
+                    prompt.hint.clear();
+                    self.draw(board).await?;
+                },
+                Input::Erase => return Ok(Input::Cancel),
+                Input::Cancel if typing => {
+                    let prompt = self.overlay.prompt.as_mut().unwrap();
+                    prompt.text.clear();
+                    prompt.hint.clear();
+                    self.draw(board).await?;
+                },
+                Input::Select if typing => {
+                    let prompt = self.overlay.prompt.as_mut().unwrap();
+                    prompt.hint.clear();
+                    return Ok(Input::Typed(std::mem::take(&mut prompt.text)));
+                },
+                Input::Select => match &self.overlay.cursor {
+                    Some(cursor) => return Ok(Input::Click(cursor.clone())),
+                    None => self.move_cursor(board, 0, 0).await?
+                },
+                input => return Ok(input)
+            }
+        }
+    }
+
+    // The cursor doesn't show up until the first key press, which puts it on one of the
+    // highlighted squares so there's usually a piece that can move right under it
+    async fn move_cursor(&mut self, board: &Board, dx: i16, dy: i16) -> Result<()> {
+        self.overlay.cursor = Some(match &self.overlay.cursor {
+            Some(cursor) => Point {
+                x: (cursor.x as i16 + dx).clamp(0, board.width() as i16 - 1) as u8,
+                y: (cursor.y as i16 + dy).clamp(0, board.height() as i16 - 1) as u8
+            },
+            None => self.overlay.highlighted.iter().min().cloned().unwrap_or(Point { x: 0, y: 0 })
+        });
+        self.draw(board).await
+    }
+
+    /// Throws away anything the player did while nobody was waiting for input, like keys pressed
+    /// while a bot was thinking, apart from asking to quit, which gets asked about now. Check
+    /// [`exit_requested`](TerminalWrapper::exit_requested) afterwards to see if they did quit.
+    pub async fn discard_input(&mut self, board: &Board) -> Result<()> {
+        while let Ok(input) = self.input_events_rx.try_recv() {
+            if let Input::Quit = input {
+                if self.confirm_quit(board).await? {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Asks whether the player really wants to quit, returning true if they do. The event loop
+    // sorts out the answer. It stops if they say yes, which closes the channel, and sends Cancel
+    // for anything else
+    async fn confirm_quit(&mut self, board: &Board) -> Result<bool> {
+        let message = std::mem::replace(&mut self.overlay.message, "Really quit? Press y to quit, or anything else to keep playing".to_string());
+        self.draw(board).await?;
+        self.overlay.message = message;
+
+        let quitting = self.input_events_rx.recv().await.is_none();
+        if !quitting {
+            self.draw(board).await?;
+        }
+        Ok(quitting)
+    }
+
+    /// Waits for the player to do something. Clicks off the board are ignored. Errors once the
+    /// player has quit, which they're asked to confirm first.
+    pub async fn next_input(&mut self, board: &Board) -> Result<Input> {
         self.terminal.execute(EnableMouseCapture)?;
         
         loop {
-            match self.click_events_rx.recv().await {
-                Some(click) => {
-                    if click.x < board.width && click.y < board.height {
+            match self.input_events_rx.recv().await {
+                Some(Input::Quit) => {
+                    if self.confirm_quit(board).await? {
                         self.terminal.execute(DisableMouseCapture)?;
-                        return Ok(click);
+                        return Err(Error::other("Input Event Channel Error"));
                     }
                 },
+                Some(Input::Click(click)) => {
+                    if click.x < board.width() && click.y < board.height() {
+                        self.terminal.execute(DisableMouseCapture)?;
+                        return Ok(Input::Click(click));
+                    }
+                },
+                Some(input) => {
+                    self.terminal.execute(DisableMouseCapture)?;
+                    return Ok(input);
+                },
                 None => {
                     self.terminal.execute(DisableMouseCapture)?;
-                    return Err(ErrorKind::new(std::io::ErrorKind::Other, "Click Event Channel Error"));
+                    return Err(Error::other("Input Event Channel Error"));
                 }
             }
         }
     }
 }
 
+// Fills in as much of the move as all the moves starting with what's been typed have in common,
+// and lists them if that's not enough to say which one it is
+fn complete(prompt: &mut Prompt, moves: &[String]) {
+    let matches: Vec<&String> = moves.iter().filter(|candidate| candidate.starts_with(&prompt.text)).collect();
+    let first = match matches.first() {
+        Some(first) => first,
+        None => {
+            prompt.hint = format!("No legal moves start with \"{}\"", prompt.text);
+            return;
+        }
+    };
+
+    let common = matches.iter().fold(first.len(), |common, candidate| {
+        first.bytes().zip(candidate.bytes()).take(common).take_while(|(a, b)| a == b).count()
+    });
+    prompt.text = first[..common].to_string();
+    prompt.hint = if matches.len() > 1 { matches.iter().map(|candidate| candidate.as_str()).collect::<Vec<&str>>().join("  ") } else { String::new() };
+}
+
 fn terminal_cord_to_board(column: u16, row: u16) -> Point {
     ((column / 2) as u8, row as u8).into()
 }
 
-async fn event_loop(exit_requested: Arc<AtomicBool>, click_events_tx: Sender<Point>) {
+// Input nobody's reading yet is dropped once the channel fills up, rather than waiting for room,
+// so the loop can always see the answer to a quit
+async fn event_loop(exit_requested: Arc<AtomicBool>, input_events_tx: Sender<Input>) {
+    let mut confirming_quit = false;
     loop {
-        match crossterm::event::read().unwrap() {
-            Event::Key(event) => {
-                if event.code == KeyCode::Esc {
-                    exit_requested.store(true, std::sync::atomic::Ordering::Relaxed);
+        // Reading blocks the thread, which would hold up the program exiting, so wait a little at
+        // a time and stop once nobody's listening any more
+        if !crossterm::event::poll(Duration::from_millis(100)).unwrap() {
+            if input_events_tx.is_closed() {
+                return;
+            }
+            continue;
+        }
+        let event = crossterm::event::read().unwrap();
+
+        // After q, y quits and any other key or click means keep playing. This loop has to be the
+        // one to stop, since it blocks the thread it's on while it waits for the next event
+        if confirming_quit {
+            match event {
+                Event::Key(event) if event.code == KeyCode::Char('y') => {
+                    exit_requested.store(true, Ordering::Relaxed);
                     return;
+                },
+                Event::Key(_) | Event::Mouse(MouseEvent { kind: MouseEventKind::Down(_), .. }) => {
+                    confirming_quit = false;
+                    input_events_tx.try_send(Input::Cancel).unwrap_or(());
+                },
+                _ => ()
+            }
+            continue;
+        }
+
+        match event {
+            Event::Key(event) => {
+                let input = match event.code {
+                    KeyCode::Char('q') => Input::Quit,
+                    KeyCode::Esc => Input::Cancel,
+                    KeyCode::Char('g') => Input::Resign, // "Give up"
+                    KeyCode::Char('u') => Input::Undo,
+                    KeyCode::Char('r') => Input::Redo,
+                    KeyCode::Left | KeyCode::Char('h') => Input::MoveCursor(-1, 0),
+                    KeyCode::Right | KeyCode::Char('l') => Input::MoveCursor(1, 0),
+                    KeyCode::Up | KeyCode::Char('k') => Input::MoveCursor(0, -1),
+                    KeyCode::Down | KeyCode::Char('j') => Input::MoveCursor(0, 1),
+                    KeyCode::Enter | KeyCode::Char(' ') => Input::Select,
+                    KeyCode::Backspace => Input::Erase,
+                    KeyCode::Char(character) if character.is_ascii_digit() || character == '-' => Input::Type(character),
+                    KeyCode::Char('x') | KeyCode::Char('X') => Input::Type('x'),
+                    KeyCode::Tab => Input::Complete,
+                    KeyCode::Home => Input::First,
+                    KeyCode::End => Input::Last,
+                    _ => continue
+                };
+
+                // Only wait for an answer if the question is going to get asked
+                let quit = matches!(input, Input::Quit);
+                if input_events_tx.try_send(input).is_ok() && quit {
+                    confirming_quit = true;
                 }
             },
             Event::Mouse(event) => {
-                if event.kind == MouseEventKind::Down(crossterm::event::MouseButton::Left) {
-                    click_events_tx.send(terminal_cord_to_board(event.column, event.row)).await.unwrap_or(());
+                if event.kind == MouseEventKind::Down(MouseButton::Left) {
+                    input_events_tx.try_send(Input::Click(terminal_cord_to_board(event.column, event.row))).unwrap_or(());
+                }
+                else if event.kind == MouseEventKind::Down(MouseButton::Right) {
+                    input_events_tx.try_send(Input::Cancel).unwrap_or(());
                 }
             },
             _ => continue
@@ -0,0 +1,41 @@
+//! A checkers engine: the board and its rules, the players that can play it, and reading and
+//! writing games in the usual draughts notations.
+//!
+//! Most things can be used straight from the crate root. A game between two of the built in
+//! players looks like this:
+//!
+//! ```
+//! use checkers::{Game, Ruleset, ActorType};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> std::io::Result<()> {
+//! let mut game = Game::new(Ruleset::american(), None)?; // No terminal, so nothing gets drawn
+//! game.seed = Some(1);
+//! let result = game.play(ActorType::Random.player(), ActorType::MostKills { prefer_crowning: true }.player()).await?;
+//! println!("{}", result.unwrap());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! New players can be plugged in by implementing [`Player`]. Squares are counted from the top
+//! left, with Red starting on the low rows. Who moves first depends on the rules.
+
+#![warn(missing_docs)]
+
+pub mod game;
+pub mod actor;
+pub mod point;
+pub mod side;
+pub mod piece;
+pub mod rules;
+pub mod search;
+pub mod mcts;
+pub mod notation;
+pub mod tournament;
+
+pub use point::Point;
+pub use side::Side;
+pub use piece::Piece;
+pub use rules::{Ruleset, Variant, DrawRules};
+pub use actor::{Action, ActionResult, Player, ActorType};
+pub use game::{Game, GameResult, Termination, DrawReason, board::Board, position::Position};
@@ -1,46 +1,149 @@
-mod game;
-mod actor;
-mod point;
-mod side;
-mod piece;
+mod cli;
 
-use std::env::args;
 use std::io::stdout;
-use crossterm::Result;
-use actor::ActorType;
-use actor::SimulatedActorType;
-use side::Side;
-use game::Game;
-
-fn str_to_actor(input: String) -> Result<ActorType> {
-    match input.to_uppercase().as_str() {
-        "S" => Ok(ActorType::Simulated(SimulatedActorType::Random)),
-        "R" => Ok(ActorType::Random),
-        "H" => Ok(ActorType::Human),
-        _ => Err(crossterm::ErrorKind::new(std::io::ErrorKind::InvalidData, "Invalid actor type code"))
+use std::{fs, path::Path};
+use std::process::ExitCode;
+use std::io::{Result, Error};
+use serde_json::json;
+use checkers::{Action, Game, GameResult, Termination, game::terminal::TerminalWrapper};
+use checkers::notation::{fen, pdn::{self, PdnResult}};
+use checkers::tournament::{self, Entrant, TournamentConfig};
+use cli::{Args, Command, TournamentArgs};
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Args::parse_and_validate();
+
+    // Everything's been dropped by the time run returns, so the terminal is back to normal
+    // before any error gets printed
+    let result = match args.command {
+        Some(Command::Tournament(tournament)) => run_tournament(tournament).await,
+        None => run(args).await
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
     }
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let mut args = args(); // Save a copy of the args
-    args.next(); // Discard the first arg since its just the command
-    let width = args.next().expect("Please provide a width").parse().expect("Please provide a valid width");
-    let height = args.next().expect("Please provide a height").parse().expect("Please provide a valid height");
+// The result of a game in a form scripts can read. The move list has every move in the game,
+// including any loaded ones, written the same way as in PDN
+fn result_json(result: &GameResult, game: &Game) -> serde_json::Value {
+    let (termination, draw_reason) = match result.termination {
+        Termination::Draw(reason) => ("draw".to_string(), Some(format!("{:?}", reason).to_lowercase())),
+        termination => (format!("{:?}", termination).to_lowercase(), None)
+    };
+    let moves: Vec<String> = game.position.history().map(|action| pdn::action_to_string(action, game.position.board().width())).collect();
+
+    json!({
+        "winner": result.winner.map(|side| format!("{:?}", side).to_lowercase()),
+        "moves": result.moves,
+        "termination": termination,
+        "draw_reason": draw_reason,
+        "move_list": moves
+    })
+}
+
+// Reads a saved game, saying which file it was if that goes wrong
+fn read_pdn(path: &Path) -> Result<pdn::PdnGame> {
+    let text = fs::read_to_string(path).map_err(|error| Error::new(error.kind(), format!("couldn't read {}: {}", path.display(), error)))?;
+    pdn::read(&text).map_err(|error| Error::new(error.kind(), format!("couldn't load {}: {}", path.display(), error)))
+}
+
+async fn run(args: Args) -> Result<()> {
+    // Replaying a saved game doesn't need any of the other options
+    if let Some(path) = &args.replay {
+        let loaded = read_pdn(path)?;
+        let mut terminal_wrapper = TerminalWrapper::new(stdout())?;
+        terminal_wrapper.replay(&loaded.start, &loaded.actions, 0, &loaded.result.to_string()).await?;
+        return Ok(());
+    }
+
+    // Tags to save along with the game. A loaded game keeps its own tags, apart from the ones
+    // that get written from the game itself
+    let mut tags = vec![
+        ("Event".to_string(), "Casual game".to_string()),
+        ("Black".to_string(), args.red.name()), // Red plays Black
+        ("White".to_string(), args.blue.name())
+    ];
+
+    // Headless games have no board to draw, so the bots don't slow down to be watched either
+    let terminal = if args.headless { None } else { Some(stdout()) };
 
-    if width < 3 { panic!("Width must be at least 3"); }
-    if height < 7 { panic!("Height must be at least 7"); }
+    let mut game = match (&args.load, &args.fen) {
+        (Some(path), _) => {
+            // The loaded game brings its own rules and board size
+            let loaded = read_pdn(path)?;
+            if loaded.result != PdnResult::Unfinished {
+                return Err(Error::new(std::io::ErrorKind::InvalidInput, "That game is already over"));
+            }
+
+            let written = ["Result", "GameType", "SetUp", "FEN"];
+            tags = loaded.tags.into_iter().filter(|(name, _)| !written.iter().any(|tag| name.eq_ignore_ascii_case(tag))).collect();
+            let mut position = loaded.start;
+            for action in &loaded.actions {
+                position.make_move(action);
+            }
+            Game::from_position(position, terminal)?
+        },
+        (None, Some(fen)) => Game::from_position(fen::read(fen, args.game.ruleset())?, terminal)?,
+        (None, None) => Game::new(args.game.ruleset(), terminal)?
+    };
+    game.seed = args.game.seed;
+    let start_ply = game.position.ply();
+
+    let result = game.play(args.game.actor_type(args.red).player(), args.game.actor_type(args.blue).player()).await?;
+
+    if let Some(path) = &args.output {
+        fs::write(path, pdn::write(&game.position, PdnResult::from(result.as_ref()), &tags))
+            .map_err(|error| Error::new(error.kind(), format!("couldn't save {}: {}", path.display(), error)))?;
+    }
+
+    // Step back through the moves played this time, if asked to and the game got finished
+    if let (true, Some(result), Some(terminal_wrapper)) = (args.review, &result, &game.terminal_wrapper) {
+        let mut start = game.position.clone();
+        while start.ply() > start_ply {
+            start.unmake_move();
+        }
+        let actions: Vec<Action> = game.position.history().skip(start_ply).cloned().collect();
+        terminal_wrapper.lock().await.replay(&start, &actions, actions.len(), &result.to_string()).await?;
+    }
+    if args.headless {
+        // Nobody can quit part way through without a terminal, so there's always a result
+        println!("{}", result_json(result.as_ref().unwrap(), &game));
+    }
+    else {
+        drop(game); // Drop the game object to restore the terminal to normal
+
+        if let Some(result) = result {
+            println!("{}", result);
+        }
+    }
+
+    Ok(())
+}
 
-    let red_actor_str = args.next().expect("Please provide an actor type for the Red player ([h]uman, [r]andom, or [s]mart)");
-    let blue_actor_str = args.next().expect("Please provide an actor type for the Blue player ([h]uman, [r]andom, or [s]mart)");
-    let red_actor = str_to_actor(red_actor_str).unwrap();
-    let blue_actor = str_to_actor(blue_actor_str).unwrap();
+async fn run_tournament(args: TournamentArgs) -> Result<()> {
+    let entrants: Vec<Entrant> = args.actors.iter().map(|kind| Entrant {
+        name: kind.name(),
+        actor_type: args.game.actor_type(*kind)
+    }).collect();
 
-    let mut game = Game::new(width, height, Some(stdout()))?;
-    let result = game.play(red_actor, blue_actor).await.unwrap().unwrap();
-    drop(game); // Drop the game object to restore the terminal to normal
+    let config = TournamentConfig {
+        rules: args.game.ruleset(),
+        games_per_pairing: args.games,
+        parallel_games: args.parallel.unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |count| count.get())),
+        seed: args.game.seed
+    };
 
-    println!("{:?}", result);
+    let records = tournament::run(&entrants, &config, |record, finished, total| {
+        eprintln!("[{}/{}] {} (Red) vs {} (Blue): {}", finished, total, entrants[record.red].name, entrants[record.blue].name, record.result);
+    }).await?;
+    print!("{}", tournament::report(&entrants, &records));
 
     Ok(())
 }
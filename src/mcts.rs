@@ -0,0 +1,260 @@
+//! Monte Carlo tree search, for finding the best move by playing lots of games out.
+
+use std::time::{Duration, Instant};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use rand::seq::SliceRandom;
+
+use crate::side::Side;
+use crate::actor::Action;
+use crate::game::{board::Board, position::Position};
+use crate::search::Evaluator;
+
+// Rollouts that go on this long without a winner are counted as draws
+const ROLLOUT_MOVE_LIMIT: usize = 200;
+
+/// How games get played out from the edge of the tree.
+#[derive(Clone, Copy, Debug)]
+pub enum Rollout {
+    /// Plays random moves.
+    Random,
+    /// Plays whichever move evaluates best one move ahead, breaking ties randomly.
+    Heuristic(Evaluator)
+}
+
+/// How hard to search. Searching stops at whichever of the iteration or time limits comes first,
+/// or after the default number of iterations if neither is set. There's always at least one
+/// iteration.
+#[derive(Clone, Copy, Debug)]
+pub struct MctsConfig {
+    /// How many times to grow the tree for each move.
+    pub iterations: Option<u32>,
+    /// How long to spend on each move.
+    pub time_limit: Option<Duration>,
+    /// The UCT exploration constant. Higher values try more moves, lower ones focus on the best.
+    pub exploration: f64,
+    /// How games get played out.
+    pub rollout: Rollout,
+    /// How many rollouts to run at once from each new leaf.
+    pub parallel_rollouts: usize
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            iterations: Some(2000),
+            time_limit: None,
+            exploration: std::f64::consts::SQRT_2,
+            rollout: Rollout::Random,
+            parallel_rollouts: 4
+        }
+    }
+}
+
+struct Node {
+    side: Side, // Whose turn it is at this node
+    action: Option<Action>, // The action that led here from the parent
+    children: Vec<Node>,
+    untried: Vec<Action>,
+    visits: u32,
+    score: f64 // Total rollout score from the point of view of whoever moved into this node
+}
+
+impl Node {
+    fn new(position: &Position, action: Option<Action>) -> Node {
+        let mut untried: Vec<Action> = position.legal_moves().into_values().flatten().collect();
+        untried.sort();
+
+        Node {
+            side: position.side_to_move(),
+            action,
+            children: Vec::new(),
+            untried,
+            visits: 0,
+            score: 0.0
+        }
+    }
+
+    fn uct(&self, parent_visits: u32, exploration: f64) -> f64 {
+        if self.visits == 0 {
+            return f64::INFINITY;
+        }
+
+        let visits = self.visits as f64;
+        (self.score / visits) + exploration * ((parent_visits as f64).ln() / visits).sqrt()
+    }
+}
+
+/// A search tree, which can be kept between turns so the work done on earlier turns isn't thrown
+/// away.
+pub struct Tree {
+    root: Node,
+    history: Vec<Action> // Every action that led to the position at the root
+}
+
+impl Tree {
+    /// An empty tree with the position at the root.
+    pub fn new(position: &Position) -> Tree {
+        Tree {
+            root: Node::new(position, None),
+            history: position.history().cloned().collect()
+        }
+    }
+
+    /// Moves the root down to the given position, if it's somewhere in the tree. Nodes only store
+    /// the actions that led to them, so this follows the position's history on from the root's.
+    /// If moves were taken back since, the root isn't on the way to the position anymore.
+    pub fn advance(self, position: &Position) -> Option<Tree> {
+        let history: Vec<Action> = position.history().cloned().collect();
+        if !history.starts_with(&self.history) {
+            return None;
+        }
+
+        let mut root = self.root;
+        for action in &history[self.history.len()..] {
+            root = root.children.into_iter().find(|child| child.action.as_ref() == Some(action))?;
+        }
+
+        Some(Tree { root, history })
+    }
+
+    /// Grows the tree for the configured budget and returns the most visited action, or None if
+    /// there are no legal moves. The position has to be the one at the root, and is left as it
+    /// was found. The same seed gives the same result as long as the search is limited by
+    /// iterations rather than time.
+    pub async fn search(&mut self, position: &mut Position, config: MctsConfig, seed: u64) -> Option<Action> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let deadline = config.time_limit.map(|limit| Instant::now() + limit);
+        let iterations = match (config.iterations, config.time_limit) {
+            (None, None) => MctsConfig::default().iterations,
+            (iterations, _) => iterations
+        };
+
+        // Always grow the tree at least once, so there's a move to pick however small the budget
+        let mut iteration = 0;
+        while iteration == 0 || (iterations.is_none_or(|limit| iteration < limit) && deadline.is_none_or(|deadline| Instant::now() < deadline)) {
+            // Selection: walk down through fully expanded nodes, picking the best child by UCT
+            let mut path: Vec<usize> = Vec::new();
+            let mut node = &mut self.root;
+            while node.untried.is_empty() && !node.children.is_empty() {
+                let parent_visits = node.visits;
+                let best_index = (0..node.children.len()).max_by(|a, b| {
+                    node.children[*a].uct(parent_visits, config.exploration)
+                        .total_cmp(&node.children[*b].uct(parent_visits, config.exploration))
+                }).unwrap();
+
+                path.push(best_index);
+                node = &mut node.children[best_index];
+                position.make_move(node.action.as_ref().unwrap());
+            }
+
+            // Expansion: add a child for one of the actions we haven't tried yet
+            if let Some(action) = node.untried.pop() {
+                position.make_move(&action);
+                node.children.push(Node::new(position, Some(action)));
+
+                path.push(node.children.len() - 1);
+                node = node.children.last_mut().unwrap();
+            }
+
+            // Simulation: play the leaf out, several times at once
+            let winners = if node.untried.is_empty() && node.children.is_empty() {
+                vec![Some(node.side.opponent()); config.parallel_rollouts.max(1)] // No need to simulate a finished game
+            }
+            else {
+                // Each rollout gets its own seed up front, so it doesn't matter what order they
+                // run in
+                let mut handles = Vec::new();
+                for _ in 0..config.parallel_rollouts.max(1) {
+                    let board = position.board().clone();
+                    let side = position.side_to_move();
+                    let rollout_seed = rng.gen();
+                    handles.push(tokio::spawn(async move { rollout(board, side, config.rollout, rollout_seed) }));
+                }
+
+                let mut winners = Vec::new();
+                for handle in handles {
+                    winners.push(handle.await.unwrap());
+                }
+                winners
+            };
+
+            // Backpropagation: credit every node on the path from the point of view of whoever
+            // moved into it, and put the position back the way it was
+            let mut node = &mut self.root;
+            backpropagate(node, &winners);
+            for index in &path {
+                node = &mut node.children[*index];
+                backpropagate(node, &winners);
+            }
+            for _ in &path {
+                position.unmake_move();
+            }
+
+            iteration += 1;
+        }
+
+        self.root.children.iter().max_by_key(|child| child.visits).and_then(|child| child.action.clone())
+    }
+}
+
+fn backpropagate(node: &mut Node, winners: &[Option<Side>]) {
+    let mover = node.side.opponent();
+    for winner in winners {
+        node.visits += 1;
+        node.score += match winner {
+            Some(side) if *side == mover => 1.0,
+            Some(_) => 0.0,
+            None => 0.5
+        };
+    }
+}
+
+// Plays the game out and returns the winner, or None if it ran too long. Rollouts only need the
+// board, so they don't bother keeping any history
+fn rollout(mut board: Board, mut side: Side, rollout: Rollout, seed: u64) -> Option<Side> {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    for _ in 0..ROLLOUT_MOVE_LIMIT {
+        // Sorted, since the moves come out of a HashMap in no particular order
+        let mut actions: Vec<Action> = board.get_all_moves(side).into_values().flatten().collect();
+        actions.sort();
+        if actions.is_empty() {
+            return Some(side.opponent());
+        }
+
+        let action = match rollout {
+            Rollout::Random => actions.choose(&mut rng).unwrap(),
+            Rollout::Heuristic(evaluation) => {
+                let scores: Vec<i32> = actions.iter().map(|action| {
+                    let mut child = board.clone();
+                    child.do_action(action);
+                    evaluation(&child, side)
+                }).collect();
+
+                let best_score = *scores.iter().max().unwrap();
+                let best: Vec<&Action> = actions.iter().zip(scores).filter(|(_, score)| *score == best_score).map(|(action, _)| action).collect();
+                best[rng.gen_range(0..best.len())]
+            }
+        };
+
+        board.do_action(action);
+        side = side.opponent();
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Ruleset;
+
+    #[tokio::test]
+    async fn search_finds_a_move_without_any_budget() {
+        let mut position = Position::new(Board::new(Ruleset::american()).unwrap(), Side::Red);
+        let config = MctsConfig { iterations: Some(0), ..MctsConfig::default() };
+
+        let action = Tree::new(&position).search(&mut position, config, 1).await;
+        assert!(action.is_some_and(|action| position.legal_moves().values().flatten().any(|legal| *legal == action)));
+    }
+}
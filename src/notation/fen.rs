@@ -0,0 +1,128 @@
+//! Draughts FEN, like "B:W21,22,K30:B1,2,K5". The first letter says who's moving, then each
+//! side's pieces are listed by square number, with a K in front of kings. As with PDN, Red plays
+//! the part of Black and Blue plays the part of White.
+
+use std::io::{Result, Error};
+use crate::{side::Side, piece::Piece, rules::Ruleset};
+use crate::game::{board::Board, position::Position};
+use super::{square_number, square_point};
+
+fn side_letter(side: Side) -> char {
+    match side {
+        Side::Red => 'B',
+        Side::Blue => 'W'
+    }
+}
+
+fn letter_side(letter: &str) -> Option<Side> {
+    match letter {
+        "B" | "b" => Some(Side::Red),
+        "W" | "w" => Some(Side::Blue),
+        _ => None
+    }
+}
+
+/// The position as a FEN string.
+pub fn write(position: &Position) -> String {
+    let board = position.board();
+    let mut fen = side_letter(position.side_to_move()).to_string();
+
+    // White's pieces are listed first by convention
+    for side in [Side::Blue, Side::Red] {
+        let mut pieces: Vec<(u16, Piece)> = board.pieces()
+            .filter(|(_, piece)| piece.side == side)
+            .map(|(point, piece)| (square_number(&point, board.width()), piece))
+            .collect();
+        pieces.sort_by_key(|(number, _)| *number);
+
+        let squares: Vec<String> = pieces.iter()
+            .map(|(number, piece)| if piece.crowned { format!("K{}", number) } else { number.to_string() })
+            .collect();
+        fen += &format!(":{}{}", side_letter(side), squares.join(","));
+    }
+
+    fen
+}
+
+/// Sets up a position on a board of the given size. The rules can't be worked out from the FEN
+/// itself, since it only lists square numbers.
+pub fn read(text: &str, rules: Ruleset) -> Result<Position> {
+    let invalid = |message: String| Error::new(std::io::ErrorKind::InvalidData, message);
+
+    let text = text.trim().trim_matches('"').trim_end_matches('.');
+    let mut fields = text.split(':').map(str::trim);
+    let side_to_move = fields.next().and_then(letter_side)
+        .ok_or_else(|| invalid(format!("Invalid side to move in \"{}\"", text)))?;
+
+    let mut board = Board::empty(rules)?;
+    for field in fields.filter(|field| !field.is_empty()) {
+        let (letter, squares) = field.split_at(field.chars().next().unwrap().len_utf8());
+        let side = letter_side(letter).ok_or_else(|| invalid(format!("Invalid side \"{}\"", field)))?;
+
+        for square in squares.split(',').map(str::trim).filter(|square| !square.is_empty()) {
+            let (crowned, square) = match square.strip_prefix(['K', 'k']) {
+                Some(square) => (true, square),
+                None => (false, square)
+            };
+
+            // Some tools write runs of men as ranges, like "1-12"
+            let (first, last) = square.split_once('-').unwrap_or((square, square));
+            let (first, last) = match (first.parse::<u16>(), last.parse::<u16>()) {
+                (Ok(first), Ok(last)) if first <= last => (first, last),
+                _ => return Err(invalid(format!("Invalid square \"{}\"", square)))
+            };
+
+            for number in first..=last {
+                let point = square_point(number, board.width(), board.height())
+                    .ok_or_else(|| invalid(format!("There's no square {} on a {}x{} board", number, board.width(), board.height())))?;
+                if board.piece_at(&point).is_some() {
+                    return Err(invalid(format!("Square {} is listed more than once", number)));
+                }
+
+                board.set_piece(&point, Some(Piece { side, crowned }));
+            }
+        }
+    }
+
+    Ok(Position::new(board, side_to_move))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point::Point;
+
+    #[test]
+    fn positions_read_back_the_same() {
+        for rules in [Ruleset::american(), Ruleset::international(), Ruleset { width: 10, height: 8, ..Ruleset::american() }] {
+            let mut position = Position::new(Board::new(rules).unwrap(), Side::Red);
+            let start = write(&position);
+            assert_eq!(write(&read(&start, rules).unwrap()), start);
+
+            // Kings and Blue to move
+            let mut board = position.board().clone();
+            board.set_piece(&Point { x: 0, y: 0 }, Some(Piece { side: Side::Blue, crowned: true }));
+            position = Position::new(board, Side::Blue);
+            let fen = write(&position);
+            let read_back = read(&fen, rules).unwrap();
+            assert_eq!(read_back.side_to_move(), Side::Blue);
+            assert_eq!(read_back.board().position_key(), position.board().position_key());
+            assert_eq!(write(&read_back), fen);
+        }
+    }
+
+    #[test]
+    fn ranges_and_lowercase_are_read() {
+        let position = read("\"b:W21-32:b1-12,k14.\"", Ruleset::american()).unwrap();
+        assert_eq!(position.side_to_move(), Side::Red);
+        assert_eq!(write(&position), "B:W21,22,23,24,25,26,27,28,29,30,31,32:B1,2,3,4,5,6,7,8,9,10,11,12,K14");
+    }
+
+    #[test]
+    fn bad_positions_are_rejected() {
+        for fen in ["X:W21:B1", "W:W21:B33", "W:W21:B1,1", "W:W21:Bx", "W:W5-3:B1", "W:Q1"] {
+            let error = read(fen, Ruleset::american()).err().unwrap();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData, "{}", fen);
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! Reading and writing games and positions the way other draughts programs do.
+//!
+//! Draughts notation numbers the dark squares one by one, row by row. Square 1 is on Red's back
+//! row, so Red's pieces start on the lowest numbers. Each row is numbered from the high x end so
+//! square 1 touches squares 5 and 6 on a standard board, the same as everyone else's numbering.
+
+pub mod pdn;
+pub mod fen;
+
+use crate::point::Point;
+
+/// The number of a dark square on a board of the given width.
+pub fn square_number(point: &Point, width: u8) -> u16 {
+    let mut number = 0;
+    for y in 0..point.y {
+        number += dark_squares_in_row(y, width);
+    }
+
+    number + ((last_dark_square(point.y, width) - point.x) / 2) as u16 + 1
+}
+
+/// The dark square with the given number, or None if the board doesn't have that many.
+pub fn square_point(number: u16, width: u8, height: u8) -> Option<Point> {
+    if number == 0 {
+        return None;
+    }
+
+    let mut remaining = number - 1;
+    for y in 0..height {
+        let in_row = dark_squares_in_row(y, width);
+        if remaining < in_row {
+            return Some(Point { x: last_dark_square(y, width) - (remaining as u8 * 2), y });
+        }
+        remaining -= in_row;
+    }
+
+    None
+}
+
+// Dark squares are the ones where x and y are both even or both odd
+fn dark_squares_in_row(y: u8, width: u8) -> u16 {
+    ((width - (y % 2)) as u16).div_ceil(2)
+}
+
+// The x of the dark square furthest along the row, where the numbering starts
+fn last_dark_square(y: u8, width: u8) -> u8 {
+    if (width - 1) % 2 == y % 2 { width - 1 } else { width - 2 }
+}
@@ -0,0 +1,384 @@
+//! Portable Draughts Notation. Red plays the part of Black in the tags and the result, and Blue
+//! plays the part of White. Results are written White first, like in chess.
+
+use std::io::{Result, Error};
+use crate::{side::Side, actor::Action, rules::{Ruleset, Variant}};
+use crate::game::{GameResult, board::Board, position::Position};
+use super::{square_number, square_point, fen};
+
+/// How a saved game ended, if it has.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PdnResult {
+    /// The side won.
+    Win(Side),
+    /// Nobody won.
+    Draw,
+    /// The game can be carried on.
+    Unfinished
+}
+
+impl From<Option<&GameResult>> for PdnResult {
+    fn from(result: Option<&GameResult>) -> Self {
+        match result {
+            Some(GameResult { winner: Some(side), .. }) => PdnResult::Win(*side),
+            Some(GameResult { winner: None, .. }) => PdnResult::Draw,
+            None => PdnResult::Unfinished
+        }
+    }
+}
+
+impl std::fmt::Display for PdnResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PdnResult::Win(side) => write!(f, "{:?} won", side),
+            PdnResult::Draw => write!(f, "Draw"),
+            PdnResult::Unfinished => write!(f, "Unfinished")
+        }
+    }
+}
+
+/// A game read from PDN.
+pub struct PdnGame {
+    /// Every tag, as name and value, in the order they were written.
+    pub tags: Vec<(String, String)>,
+    /// The position before the first move.
+    pub start: Position,
+    /// Every move, in order.
+    pub actions: Vec<Action>,
+    /// How the game ended, if it has.
+    pub result: PdnResult
+}
+
+// The standard game type numbers for each variant
+fn game_type(variant: Variant) -> u8 {
+    match variant {
+        Variant::International => 20,
+        Variant::American => 21,
+        Variant::Pool => 23,
+        Variant::Russian => 25,
+        Variant::Brazilian => 26
+    }
+}
+
+fn variant(game_type: u8) -> Option<Variant> {
+    match game_type {
+        20 => Some(Variant::International),
+        21 => Some(Variant::American),
+        23 => Some(Variant::Pool),
+        25 => Some(Variant::Russian),
+        26 => Some(Variant::Brazilian),
+        _ => None
+    }
+}
+
+fn result_token(result: PdnResult) -> &'static str {
+    match result {
+        PdnResult::Win(Side::Blue) => "1-0",
+        PdnResult::Win(Side::Red) => "0-1",
+        PdnResult::Draw => "1/2-1/2",
+        PdnResult::Unfinished => "*"
+    }
+}
+
+fn parse_result_token(token: &str) -> Option<PdnResult> {
+    match token {
+        "1-0" | "2-0" => Some(PdnResult::Win(Side::Blue)),
+        "0-1" | "0-2" => Some(PdnResult::Win(Side::Red)),
+        "1/2-1/2" | "1-1" => Some(PdnResult::Draw),
+        "*" => Some(PdnResult::Unfinished),
+        _ => None
+    }
+}
+
+/// Writes a move as its squares joined by "-" for a plain move or "x" for a capture, listing every
+/// square landed on so multi-jumps are never ambiguous.
+pub fn action_to_string(action: &Action, width: u8) -> String {
+    let separator = if action.is_capture() { "x" } else { "-" };
+    std::iter::once(&action.from).chain(action.path.iter())
+        .map(|point| square_number(point, width).to_string())
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+/// Finds the legal move a written move refers to. Captures can leave out the squares in between,
+/// as long as that doesn't make the move ambiguous.
+pub fn parse_action(text: &str, position: &Position) -> Result<Action> {
+    let board = position.board();
+    let squares = text.split(['-', 'x', 'X'])
+        .map(|square| square.parse::<u16>().ok().and_then(|number| square_point(number, board.width(), board.height())))
+        .collect::<Option<Vec<_>>>()
+        .filter(|squares| squares.len() >= 2)
+        .ok_or_else(|| Error::new(std::io::ErrorKind::InvalidData, format!("Invalid move \"{}\"", text)))?;
+
+    let matches: Vec<Action> = position.legal_moves().into_values().flatten().filter(|action| {
+        let hops = &squares[1..];
+        action.from == squares[0] && action.to() == hops.last().unwrap()
+            && (hops.len() == 1 || action.path == hops)
+    }).collect();
+
+    match matches.len() {
+        1 => Ok(matches.into_iter().next().unwrap()),
+        0 => Err(Error::new(std::io::ErrorKind::InvalidData, format!("Illegal move \"{}\"", text))),
+        _ => Err(Error::new(std::io::ErrorKind::InvalidData, format!("Ambiguous move \"{}\"", text)))
+    }
+}
+
+/// Writes out every move made in the position along with the given tags.
+pub fn write(position: &Position, result: PdnResult, tags: &[(String, String)]) -> String {
+    let mut pdn = String::new();
+    for (name, value) in tags {
+        pdn += &format!("[{} \"{}\"]\n", name, value.replace('"', "'"));
+    }
+    // Boards that aren't the variant's usual size need the long form of the game type, which
+    // also says who moves first, the board size and how the squares are numbered
+    let rules = position.board().rules();
+    let default_rules = Ruleset::from(rules.variant);
+    if rules.width == default_rules.width && rules.height == default_rules.height {
+        pdn += &format!("[GameType \"{}\"]\n", game_type(rules.variant));
+    }
+    else {
+        let first = if rules.first_mover == Side::Red { "B" } else { "W" };
+        pdn += &format!("[GameType \"{},{},{},{},N1,0\"]\n", game_type(rules.variant), first, rules.width, rules.height);
+    }
+    pdn += &format!("[Result \"{}\"]\n", result_token(result));
+
+    // Games that didn't start from the usual setup say where they did start
+    let mut start = position.clone();
+    while start.unmake_move().is_some() {}
+    let standard_start = Board::new(rules).map(|board| board.position_key() == start.board().position_key()).unwrap_or(false);
+    let first = rules.first_mover;
+    if !standard_start || start.side_to_move() != first {
+        pdn += "[SetUp \"1\"]\n";
+        pdn += &format!("[FEN \"{}\"]\n", fen::write(&start));
+    }
+    pdn += "\n";
+
+    // Work out who made the first move from who's moving now. Each move number starts with the
+    // variant's usual first mover
+    let actions: Vec<&Action> = position.history().collect();
+    let mut side = if actions.len().is_multiple_of(2) { position.side_to_move() } else { position.side_to_move().opponent() };
+    let second_started = (side != first) as usize;
+
+    // Move numbers stay attached to their move so they don't get wrapped onto a separate line
+    let mut tokens = Vec::new();
+    for (index, action) in actions.iter().enumerate() {
+        let move_number = ((index + second_started) / 2) + 1;
+        let action = action_to_string(action, position.board().width());
+        if side == first {
+            tokens.push(format!("{}. {}", move_number, action));
+        }
+        else if index == 0 {
+            tokens.push(format!("{}... {}", move_number, action)); // The game started with the other side to move
+        }
+        else {
+            tokens.push(action);
+        }
+
+        side = side.opponent();
+    }
+    tokens.push(result_token(result).to_string());
+
+    // Wrap the move text so lines don't get too long
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.len() + token.len() >= 80 {
+            pdn += &line;
+            pdn += "\n";
+            line.clear();
+        }
+        if !line.is_empty() {
+            line += " ";
+        }
+        line += &token;
+    }
+    pdn += &line;
+    pdn += "\n";
+
+    pdn
+}
+
+/// Reads the first game in the text, checking every move against the rules.
+pub fn read(text: &str) -> Result<PdnGame> {
+    let invalid = |message: String| Error::new(std::io::ErrorKind::InvalidData, message);
+
+    // Tags come first, one per line in square brackets
+    let mut tags = Vec::new();
+    let mut lines = text.lines().map(str::trim).peekable();
+    while let Some(line) = lines.peek() {
+        if line.is_empty() {
+            lines.next();
+            continue;
+        }
+        if !line.starts_with('[') {
+            break;
+        }
+
+        let inner = line.trim_start_matches('[').trim_end_matches(']');
+        let (name, value) = inner.split_once(' ').ok_or_else(|| invalid(format!("Invalid tag \"{}\"", line)))?;
+        tags.push((name.to_string(), value.trim().trim_matches('"').to_string()));
+        lines.next();
+    }
+
+    let rules = match tags.iter().find(|(name, _)| name.eq_ignore_ascii_case("GameType")) {
+        Some((_, value)) => {
+            let fields: Vec<&str> = value.split(',').map(str::trim).collect();
+            let rules = fields[0].parse().ok().and_then(variant).map(Ruleset::from)
+                .ok_or_else(|| invalid(format!("Unsupported game type \"{}\"", value)))?;
+
+            // The long form also gives who moves first and the board size
+            let first_mover = match fields.get(1) {
+                Some(&"W") | Some(&"w") => Side::Blue,
+                Some(&"B") | Some(&"b") => Side::Red,
+                _ => rules.first_mover
+            };
+            match (fields.get(2).and_then(|width| width.parse().ok()), fields.get(3).and_then(|height| height.parse().ok())) {
+                (Some(width), Some(height)) => Ruleset { width, height, first_mover, ..rules },
+                _ => Ruleset { first_mover, ..rules }
+            }
+        },
+        None => Ruleset::american()
+    };
+    if let Some(problem) = rules.size_problem() {
+        return Err(invalid(format!("Unsupported board size {}x{}: {}", rules.width, rules.height, problem)));
+    }
+    let start = match tags.iter().find(|(name, _)| name.eq_ignore_ascii_case("FEN")) {
+        Some((_, value)) => fen::read(value, rules)?,
+        None => Position::new(Board::new(rules)?, rules.first_mover)
+    };
+
+    // Comments in braces can span lines, so strip them before splitting the move text up
+    let mut move_text = String::new();
+    let mut in_comment = false;
+    for character in lines.collect::<Vec<&str>>().join(" ").chars() {
+        match character {
+            '{' => in_comment = true,
+            '}' => in_comment = false,
+            _ if !in_comment => move_text.push(character),
+            _ => ()
+        }
+    }
+
+    let mut position = start.clone();
+    let mut actions = Vec::new();
+    let mut result = PdnResult::Unfinished;
+    for token in move_text.split_whitespace() {
+        if let Some(token_result) = parse_result_token(token) {
+            result = token_result;
+            break; // The result marks the end of the game
+        }
+
+        // Skip move numbers, and drop any annotations like "!" or "?" off the end of moves
+        let token = token.trim_end_matches(['!', '?']);
+        let token = match token.rsplit_once('.') {
+            Some((_, rest)) => rest,
+            None => token
+        };
+        if token.is_empty() {
+            continue;
+        }
+
+        let action = parse_action(token, &position)?;
+        position.make_move(&action);
+        actions.push(action);
+    }
+
+    Ok(PdnGame {
+        tags,
+        start,
+        actions,
+        result
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    // A position some random moves into a game, so there are captures and hopefully kings
+    fn played(rules: Ruleset, plies: usize) -> Position {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut position = Position::new(Board::new(rules).unwrap(), rules.first_mover);
+        for _ in 0..plies {
+            let mut moves: Vec<Action> = position.legal_moves().into_values().flatten().collect();
+            if moves.is_empty() {
+                break;
+            }
+            moves.sort();
+            let action = moves.swap_remove(rng.gen_range(0..moves.len()));
+            position.make_move(&action);
+        }
+        position
+    }
+
+    #[test]
+    fn games_read_back_the_same() {
+        let sized = Ruleset { width: 10, height: 8, first_mover: Side::Blue, ..Ruleset::american() };
+        for rules in [Ruleset::american(), Ruleset::international(), Ruleset::russian(), Ruleset::brazilian(), Ruleset::pool(), sized] {
+            let position = played(rules, 80);
+            let tags = vec![("Event".to_string(), "Test \"game\"".to_string())];
+            let text = write(&position, PdnResult::Win(Side::Blue), &tags);
+            let game = read(&text).unwrap();
+
+            assert_eq!(game.start.board().rules(), rules);
+            assert_eq!(game.tags[0], ("Event".to_string(), "Test 'game'".to_string()));
+            assert_eq!(game.result, PdnResult::Win(Side::Blue));
+            assert_eq!(game.actions, position.history().cloned().collect::<Vec<Action>>());
+
+            let mut replayed = game.start;
+            for action in &game.actions {
+                replayed.make_move(action);
+            }
+            assert_eq!(fen::write(&replayed), fen::write(&position));
+        }
+    }
+
+    #[test]
+    fn games_from_a_set_up_position_read_back_the_same() {
+        let mut position = fen::read("W:WK5,18,22:B9,K30", Ruleset::american()).unwrap();
+        let action = position.legal_moves().into_values().flatten().next().unwrap();
+        position.make_move(&action);
+
+        let game = read(&write(&position, PdnResult::Unfinished, &[])).unwrap();
+        assert_eq!(fen::write(&game.start), "W:WK5,18,22:B9,K30");
+        assert_eq!(game.actions, vec![action]);
+        assert_eq!(game.result, PdnResult::Unfinished);
+    }
+
+    #[test]
+    fn board_sizes_that_cant_be_played_are_rejected() {
+        for game_type in ["21,B,0,8,N1,0", "21,B,8,0,N1,0", "21,B,2,8,N1,0", "20,W,10,5,N2,0", "21,B,16,16,N1,0"] {
+            let error = read(&format!("[GameType \"{}\"]\n\n*", game_type)).err().unwrap();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData, "{}", game_type);
+        }
+    }
+
+    #[test]
+    fn white_moves_first_in_international() {
+        // Without a FEN, the usual setup has White moving first
+        let game = read("[GameType \"20\"]\n\n1. 32-28 19-23 *").unwrap();
+        assert_eq!(game.start.side_to_move(), Side::Blue);
+        assert_eq!(game.actions.len(), 2);
+
+        // So a game from the usual setup doesn't need one
+        let mut position = Position::new(Board::new(Ruleset::international()).unwrap(), Side::Blue);
+        position.make_move(&parse_action("32-28", &position).unwrap());
+        let text = write(&position, PdnResult::Unfinished, &[]);
+        assert!(!text.contains("[FEN"));
+        assert!(text.contains("1. 32-28 *"));
+
+        // But one where Black moves first does
+        let mut position = Position::new(Board::new(Ruleset::international()).unwrap(), Side::Red);
+        position.make_move(&parse_action("17-21", &position).unwrap());
+        let text = write(&position, PdnResult::Unfinished, &[]);
+        assert!(text.contains("[FEN \"B:"));
+        assert!(text.contains("1... 17-21 *"));
+        assert_eq!(read(&text).unwrap().start.side_to_move(), Side::Red);
+    }
+
+    #[test]
+    fn long_game_types_say_who_moves_first() {
+        let rules = read("[GameType \"21,W,10,8,N1,0\"]\n\n*").unwrap().start.board().rules();
+        assert_eq!(rules, Ruleset { width: 10, height: 8, first_mover: Side::Blue, ..Ruleset::american() });
+    }
+}
@@ -1,7 +1,12 @@
+//! A single piece on the board.
+
 use crate::side::Side;
 
-#[derive(Copy, Clone)]
+/// A man, or a king if it's been crowned.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Piece {
+    /// Who the piece belongs to.
     pub side: Side,
+    /// Kings can move backwards, and with some rules fly along whole diagonals.
     pub crowned: bool
 }
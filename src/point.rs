@@ -1,6 +1,12 @@
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+//! Board coordinates.
+
+/// A square on the board, counted from the top left. Dark squares, the only ones pieces can stand
+/// on, are the ones where x and y are both even or both odd.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Point {
+    /// The column.
     pub x: u8,
+    /// The row. Red starts on the low rows.
     pub y: u8
 }
 
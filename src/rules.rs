@@ -0,0 +1,191 @@
+//! The rules that differ between variants of draughts, and when a game counts as drawn.
+
+use crate::side::Side;
+use crate::game::board::MAX_SQUARES;
+
+/// The draughts variants with built in rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    /// Also known as English draughts. The default.
+    American,
+    /// Played on a 10x10 board.
+    International,
+    /// Russian draughts.
+    Russian,
+    /// Brazilian draughts.
+    Brazilian,
+    /// Pool checkers.
+    Pool
+}
+
+/// What happens when a man reaches the far row partway through a jump chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidChainPromotion {
+    /// The man is crowned and the chain stops there.
+    EndsTurn,
+    /// The man keeps jumping as a man, and is only crowned if the chain ends on the far row.
+    PassesThrough,
+    /// The man is crowned immediately and keeps jumping as a king.
+    Crowns
+}
+
+/// Everything about how a game is played. Start from one of the variants and change the board
+/// size if needed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ruleset {
+    /// Which variant these rules are based on, for writing down which game is being played.
+    pub variant: Variant,
+    /// How many squares across the board is.
+    pub width: u8,
+    /// How many squares tall the board is.
+    pub height: u8,
+    /// How many rows each side starts with.
+    pub piece_rows: u8,
+    /// Who moves first from the starting position. Red plays Black and Blue plays White.
+    pub first_mover: Side,
+    /// Kings can move and capture along a whole diagonal instead of a single step.
+    pub flying_kings: bool,
+    /// Men can capture backwards, though they still only move forwards.
+    pub men_capture_backward: bool,
+    /// Only the capture chains that take the most pieces are allowed.
+    pub majority_capture: bool,
+    /// What happens when a man reaches the far row partway through a jump chain.
+    pub mid_chain_promotion: MidChainPromotion
+}
+
+impl Ruleset {
+    /// American or English draughts on an 8x8 board.
+    pub fn american() -> Ruleset {
+        Ruleset {
+            variant: Variant::American,
+            width: 8,
+            height: 8,
+            piece_rows: 3,
+            first_mover: Side::Red,
+            flying_kings: false,
+            men_capture_backward: false,
+            majority_capture: false,
+            mid_chain_promotion: MidChainPromotion::EndsTurn
+        }
+    }
+
+    /// International draughts on a 10x10 board.
+    pub fn international() -> Ruleset {
+        Ruleset {
+            variant: Variant::International,
+            width: 10,
+            height: 10,
+            piece_rows: 4,
+            first_mover: Side::Blue,
+            flying_kings: true,
+            men_capture_backward: true,
+            majority_capture: true,
+            mid_chain_promotion: MidChainPromotion::PassesThrough
+        }
+    }
+
+    /// Russian draughts on an 8x8 board.
+    pub fn russian() -> Ruleset {
+        Ruleset {
+            variant: Variant::Russian,
+            width: 8,
+            height: 8,
+            piece_rows: 3,
+            first_mover: Side::Blue,
+            flying_kings: true,
+            men_capture_backward: true,
+            majority_capture: false,
+            mid_chain_promotion: MidChainPromotion::Crowns
+        }
+    }
+
+    /// International rules on an 8x8 board.
+    pub fn brazilian() -> Ruleset {
+        Ruleset {
+            variant: Variant::Brazilian,
+            width: 8,
+            height: 8,
+            piece_rows: 3,
+            ..Ruleset::international()
+        }
+    }
+
+    /// Pool checkers on an 8x8 board.
+    pub fn pool() -> Ruleset {
+        Ruleset {
+            variant: Variant::Pool,
+            width: 8,
+            height: 8,
+            piece_rows: 3,
+            first_mover: Side::Red,
+            flying_kings: true,
+            men_capture_backward: true,
+            majority_capture: false,
+            mid_chain_promotion: MidChainPromotion::PassesThrough
+        }
+    }
+
+    /// Looks up a variant's rules by name, ignoring case. "english" works too.
+    pub fn from_name(name: &str) -> Option<Ruleset> {
+        match name.to_lowercase().as_str() {
+            "american" | "english" => Some(Ruleset::american()),
+            "international" => Some(Ruleset::international()),
+            "russian" => Some(Ruleset::russian()),
+            "brazilian" => Some(Ruleset::brazilian()),
+            "pool" => Some(Ruleset::pool()),
+            _ => None
+        }
+    }
+
+    /// Why the board size won't work with these rules, or None if it will. Boards need room for
+    /// both sides' pieces with a row between them, and at most [`MAX_SQUARES`] squares.
+    pub fn size_problem(&self) -> Option<String> {
+        let min_height = (self.piece_rows * 2) + 1;
+        if self.width < 3 {
+            Some("the width must be at least 3".to_string())
+        }
+        else if self.height < min_height {
+            Some(format!("the height must be at least {} for these rules", min_height))
+        }
+        else if self.width as usize * self.height as usize > MAX_SQUARES {
+            Some(format!("boards can have at most {} squares", MAX_SQUARES))
+        }
+        else {
+            None
+        }
+    }
+}
+
+impl From<Variant> for Ruleset {
+    fn from(variant: Variant) -> Self {
+        match variant {
+            Variant::American => Ruleset::american(),
+            Variant::International => Ruleset::international(),
+            Variant::Russian => Ruleset::russian(),
+            Variant::Brazilian => Ruleset::brazilian(),
+            Variant::Pool => Ruleset::pool()
+        }
+    }
+}
+
+/// When a game should be called a draw. Move counts are in plies, i.e. each side's turn counts
+/// as a move. None turns a rule off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrawRules {
+    /// Draw once the same position comes up this many times.
+    pub repetitions: Option<usize>,
+    /// Draw after this many moves without a capture or a man moving.
+    pub no_progress_limit: Option<usize>,
+    /// Draw after this many moves no matter what.
+    pub move_limit: Option<usize>
+}
+
+impl Default for DrawRules {
+    fn default() -> Self {
+        DrawRules {
+            repetitions: Some(3),
+            no_progress_limit: Some(80), // 40 moves per side
+            move_limit: None
+        }
+    }
+}
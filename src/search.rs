@@ -0,0 +1,291 @@
+//! Alpha-beta search, for finding the best move by looking ahead.
+
+use std::time::{Duration, Instant};
+
+use crate::side::Side;
+use crate::point::Point;
+use crate::actor::Action;
+use crate::rules::DrawRules;
+use crate::game::{board::Board, position::Position};
+
+// Scores at or beyond this mean someone is out of moves
+const WIN_SCORE: i32 = 1_000_000;
+
+// How many nodes to search between checks of the clock
+const NODES_PER_TIME_CHECK: u64 = 1024;
+
+/// Scores a board from the given side's point of view, higher being better for that side. Any
+/// function that doesn't capture anything will do, including closures.
+pub type Evaluator = fn(&Board, Side) -> i32;
+
+/// The default [`Evaluator`], which scores the board with the default [`Evaluation`] weights.
+pub fn weighted(board: &Board, side: Side) -> i32 {
+    Evaluation::default().evaluate(board, side)
+}
+
+/// How much each feature of a position is worth. Setting a weight to 0 turns that feature off.
+/// Other weights can be used as an [`Evaluator`] with a closure like
+/// `|board, side| Evaluation { king: 200, ..Evaluation::default() }.evaluate(board, side)`.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Evaluation {
+    /// Per man.
+    pub man: i32,
+    /// Per king.
+    pub king: i32,
+    /// Per row a man has moved forward.
+    pub advancement: i32,
+    /// Per piece in the middle of the board.
+    pub center: i32
+}
+
+impl Default for Evaluation {
+    fn default() -> Self {
+        Evaluation {
+            man: 100,
+            king: 160,
+            advancement: 4,
+            center: 6
+        }
+    }
+}
+
+impl Evaluation {
+    /// Scores the board from the given side's point of view.
+    pub fn evaluate(&self, board: &Board, side: Side) -> i32 {
+        let mut score = 0;
+        for (Point { x, y }, piece) in board.pieces() {
+            let mut value = if piece.crowned { self.king } else { self.man };
+
+            if !piece.crowned {
+                let rows_advanced = match piece.side {
+                    Side::Red => y,
+                    Side::Blue => board.height() - 1 - y
+                };
+                value += self.advancement * rows_advanced as i32;
+            }
+
+            let in_center = x >= board.width() / 4 && x < board.width() - board.width() / 4
+                && y >= board.height() / 4 && y < board.height() - board.height() / 4;
+            if in_center {
+                value += self.center;
+            }
+
+            if piece.side == side {
+                score += value;
+            }
+            else {
+                score -= value;
+            }
+        }
+
+        score
+    }
+}
+
+/// How hard to search. Searching stops at whichever of the depth or time limits comes first.
+#[derive(Clone, Copy, Debug)]
+pub struct SearchConfig {
+    /// How many moves ahead to look, counting each side's turn separately.
+    pub depth: u8,
+    /// How long to spend on each move, if there's a limit.
+    pub time_limit: Option<Duration>,
+    /// How positions at the end of the search get scored.
+    pub evaluation: Evaluator
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            depth: 6,
+            time_limit: None,
+            evaluation: weighted
+        }
+    }
+}
+
+struct SearchContext {
+    config: SearchConfig,
+    draw_rules: DrawRules,
+    deadline: Option<Instant>,
+    nodes: u64,
+    out_of_time: bool
+}
+
+impl SearchContext {
+    fn check_time(&mut self) -> bool {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(NODES_PER_TIME_CHECK) {
+            if let Some(deadline) = self.deadline {
+                self.out_of_time = Instant::now() >= deadline;
+            }
+        }
+
+        self.out_of_time
+    }
+}
+
+/// Finds the best action for the side to move using iterative deepening negamax with alpha-beta
+/// pruning. Returns None if the side can't move. Lines that the draw rules would end in a draw
+/// score as even. The position is left as it was found.
+pub fn best_action(position: &mut Position, config: SearchConfig, draw_rules: DrawRules) -> Option<Action> {
+    let mut context = SearchContext {
+        config,
+        draw_rules,
+        deadline: config.time_limit.map(|limit| Instant::now() + limit),
+        nodes: 0,
+        out_of_time: false
+    };
+
+    let mut actions = ordered_actions(position);
+    if actions.is_empty() {
+        return None;
+    }
+
+    let mut best = actions[0].clone();
+    for depth in 1..=config.depth.max(1) {
+        let mut alpha = -WIN_SCORE - 1;
+        let mut depth_best = None;
+
+        for action in &actions {
+            position.make_move(action);
+            let score = negamax(position, depth - 1, -WIN_SCORE - 1, -alpha, 1, &mut context);
+            position.unmake_move();
+
+            let score = match score {
+                Some(score) => -score,
+                None => break // Out of time, so this depth can't be trusted
+            };
+
+            if score > alpha {
+                alpha = score;
+                depth_best = Some(action.clone());
+            }
+        }
+
+        if context.out_of_time {
+            break;
+        }
+
+        best = depth_best.unwrap();
+
+        // Search the best action first next time round, since it'll likely still be best
+        let best_index = actions.iter().position(|action| *action == best).unwrap();
+        let best_action = actions.remove(best_index);
+        actions.insert(0, best_action);
+
+        // No point looking deeper once a forced win or loss has been found
+        if alpha.abs() >= WIN_SCORE - config.depth as i32 {
+            break;
+        }
+    }
+
+    Some(best)
+}
+
+// Scores the position from the point of view of the side to move, or returns None if the search
+// ran out of time
+fn negamax(position: &mut Position, depth: u8, mut alpha: i32, beta: i32, ply: i32, context: &mut SearchContext) -> Option<i32> {
+    if context.check_time() {
+        return None;
+    }
+
+    // The game ends in a draw before anyone gets to move, just like in Game::play
+    let rules = context.draw_rules;
+    if rules.repetitions.is_some_and(|limit| position.repetitions() >= limit)
+        || rules.no_progress_limit.is_some_and(|limit| position.moves_without_progress() >= limit) {
+        return Some(0);
+    }
+
+    let actions = ordered_actions(position);
+    if actions.is_empty() {
+        return Some(-WIN_SCORE + ply); // Losing later is better than losing sooner
+    }
+
+    // Keep going past the depth limit while captures are pending, otherwise the evaluation would
+    // miss pieces that are about to be taken. Captures are mandatory, so if the first action isn't
+    // a capture none of them are
+    if depth == 0 && !actions[0].is_capture() {
+        return Some((context.config.evaluation)(position.board(), position.side_to_move()));
+    }
+
+    for action in actions {
+        position.make_move(&action);
+        let score = negamax(position, depth.saturating_sub(1), -beta, -alpha, ply + 1, context);
+        position.unmake_move();
+
+        let score = -score?;
+        if score >= beta {
+            return Some(score);
+        }
+        alpha = alpha.max(score);
+    }
+
+    Some(alpha)
+}
+
+// Every legal action for the side, with captures first since they tend to cause more cutoffs.
+// Otherwise they're sorted so the search always visits them in the same order
+fn ordered_actions(position: &Position) -> Vec<Action> {
+    let mut actions: Vec<Action> = position.legal_moves().into_values().flatten().collect();
+    actions.sort_by(|a, b| b.captures.len().cmp(&a.captures.len()).then(a.cmp(b)));
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{piece::Piece, rules::Ruleset};
+
+    fn step(from: (u8, u8), to: (u8, u8)) -> Action {
+        Action { from: from.into(), path: vec![to.into()], captures: Vec::new() }
+    }
+
+    // Red's two kings against Blue's one, with the kings in the corners having shuffled back and
+    // forth so the starting position has come up twice
+    fn shuffled() -> Position {
+        let mut board = Board::empty(Ruleset::american()).unwrap();
+        board.set_piece(&(0, 0).into(), Some(Piece { side: Side::Red, crowned: true }));
+        board.set_piece(&(2, 0).into(), Some(Piece { side: Side::Red, crowned: true }));
+        board.set_piece(&(7, 7).into(), Some(Piece { side: Side::Blue, crowned: true }));
+
+        let mut position = Position::new(board, Side::Red);
+        for _ in 0..2 {
+            position.make_move(&step((0, 0), (1, 1)));
+            position.make_move(&step((7, 7), (6, 6)));
+            position.make_move(&step((1, 1), (0, 0)));
+            position.make_move(&step((6, 6), (7, 7)));
+        }
+        position
+    }
+
+    #[test]
+    fn losing_side_repeats_for_a_draw() {
+        let mut position = shuffled();
+        position.make_move(&step((0, 0), (1, 1)));
+        position.make_move(&step((7, 7), (6, 6)));
+        position.make_move(&step((1, 1), (0, 0)));
+
+        // Going back to the corner repeats the start a third time
+        let action = best_action(&mut position, SearchConfig::default(), DrawRules::default());
+        assert_eq!(action, Some(step((6, 6), (7, 7))));
+    }
+
+    #[test]
+    fn no_progress_counts_as_a_draw() {
+        let mut position = shuffled();
+        let draw_rules = DrawRules { repetitions: None, no_progress_limit: Some(position.moves_without_progress() + 1), move_limit: None };
+        let mut context = SearchContext { config: SearchConfig::default(), draw_rules, deadline: None, nodes: 0, out_of_time: false };
+
+        // Whatever Red does, the game's drawn once it's done
+        assert_eq!(negamax(&mut position, 4, -WIN_SCORE - 1, WIN_SCORE + 1, 0, &mut context), Some(0));
+    }
+
+    #[test]
+    fn evaluation_can_be_swapped_out() {
+        // Scoring every position the same means the first of the sorted moves gets picked
+        let mut position = shuffled();
+        let config = SearchConfig { depth: 2, evaluation: |_, _| 0, ..SearchConfig::default() };
+        let first = ordered_actions(&position).remove(0);
+        assert_eq!(best_action(&mut position, config, DrawRules::default()), Some(first));
+    }
+}
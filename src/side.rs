@@ -1,12 +1,26 @@
+//! The two sides of a game.
+
 use crate::piece::Piece;
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+/// One of the two players. Red starts on the low rows, and the rules say who moves first.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Side {
+    /// Plays the part of Black in draughts notation.
     Red,
+    /// Plays the part of White in draughts notation.
     Blue
 }
 
 impl Side {
+    /// The other side.
+    pub fn opponent(&self) -> Side {
+        match self {
+            Side::Red => Side::Blue,
+            Side::Blue => Side::Red
+        }
+    }
+
+    /// Whether there's a piece and it belongs to this side.
     pub fn piece_is_friendly(&self, piece: &Option<Piece>) -> bool {
         match piece {
             None => false,
@@ -16,6 +30,7 @@ impl Side {
         }
     }
 
+    /// Whether there's a piece and it belongs to the other side.
     pub fn piece_is_hostile(&self, piece: &Option<Piece>) -> bool {
         match piece {
             None => false,
@@ -24,6 +39,4 @@ impl Side {
             }
         }
     }
-
 }
-
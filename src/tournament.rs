@@ -0,0 +1,221 @@
+//! Round robin tournaments between bots, for seeing which is strongest.
+
+use std::sync::Arc;
+use tokio::{sync::Semaphore, task::JoinSet};
+use std::io::{Result, Error};
+use crate::{actor::ActorType, side::Side, rules::Ruleset};
+use crate::game::{Game, GameResult};
+
+// How many standard errors either side of the score the confidence intervals cover, for 95%
+const CONFIDENCE_Z: f64 = 1.96;
+
+/// A bot taking part in a tournament.
+pub struct Entrant {
+    /// What the bot is called in the results.
+    pub name: String,
+    /// Which bot it is.
+    pub actor_type: ActorType
+}
+
+/// How a tournament is run.
+pub struct TournamentConfig {
+    /// The rules every game is played by.
+    pub rules: Ruleset,
+    /// How many games each pair of entrants plays.
+    pub games_per_pairing: usize,
+    /// How many games can be running at once.
+    pub parallel_games: usize,
+    /// Each game gets its own seed counting up from this one.
+    pub seed: Option<u64>
+}
+
+/// A finished game, with the players given as indexes into the entrants.
+pub struct GameRecord {
+    /// Who played Red.
+    pub red: usize,
+    /// Who played Blue.
+    pub blue: usize,
+    /// How the game went.
+    pub result: GameResult
+}
+
+impl GameRecord {
+    /// Who won, or None for a draw.
+    pub fn winner(&self) -> Option<usize> {
+        match self.result.winner {
+            Some(Side::Red) => Some(self.red),
+            Some(Side::Blue) => Some(self.blue),
+            None => None
+        }
+    }
+}
+
+/// Plays every entrant against every other one, swapping who's Red each game. Tournaments can take
+/// a while, so `on_game_finished` is called with each game as it finishes, along with how many
+/// have finished so far and how many there are in total.
+pub async fn run(entrants: &[Entrant], config: &TournamentConfig, mut on_game_finished: impl FnMut(&GameRecord, usize, usize)) -> Result<Vec<GameRecord>> {
+    let semaphore = Arc::new(Semaphore::new(config.parallel_games));
+    let mut games = JoinSet::new();
+
+    let mut game_number = 0;
+    for first in 0..entrants.len() {
+        for second in (first + 1)..entrants.len() {
+            for round in 0..config.games_per_pairing {
+                let (red, blue) = if round % 2 == 0 { (first, second) } else { (second, first) };
+                let (red_type, blue_type) = (entrants[red].actor_type, entrants[blue].actor_type);
+                let seed = config.seed.map(|seed| seed.wrapping_add(game_number));
+                let rules = config.rules;
+                let semaphore = Arc::clone(&semaphore);
+
+                games.spawn(async move {
+                    let _permit = semaphore.acquire_owned().await.unwrap();
+
+                    let mut game = Game::new(rules, None)?;
+                    game.seed = seed;
+                    let result = game.play(red_type.player(), blue_type.player()).await?.unwrap(); // Games without a terminal always finish
+                    Ok::<GameRecord, Error>(GameRecord { red, blue, result })
+                });
+                game_number += 1;
+            }
+        }
+    }
+
+    let total = games.len();
+    let mut records = Vec::new();
+    while let Some(record) = games.join_next().await {
+        let record = record.map_err(|error| Error::other(error.to_string()))??;
+        on_game_finished(&record, records.len() + 1, total);
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+#[derive(Default, Clone, Copy)]
+struct Tally {
+    wins: usize,
+    draws: usize,
+    losses: usize
+}
+
+impl Tally {
+    fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    // Wins count as 1 and draws as a half
+    fn score(&self) -> f64 {
+        (self.wins as f64 + (self.draws as f64 / 2.0)) / self.games() as f64
+    }
+
+    // The rating difference that would give this score, along with the bounds of its confidence
+    // interval. Perfect and zero scores give infinite differences
+    fn elo(&self) -> (f64, f64, f64) {
+        let score = self.score();
+        let games = self.games() as f64;
+        let variance = ((self.wins as f64 * (1.0 - score).powi(2))
+            + (self.draws as f64 * (0.5 - score).powi(2))
+            + (self.losses as f64 * score.powi(2))) / games;
+        let margin = CONFIDENCE_Z * (variance / games).sqrt();
+
+        (elo_difference(score), elo_difference((score - margin).max(0.0)), elo_difference((score + margin).min(1.0)))
+    }
+}
+
+fn elo_difference(score: f64) -> f64 {
+    -400.0 * ((1.0 / score) - 1.0).log10()
+}
+
+fn format_elo(elo: f64) -> String {
+    if elo.is_infinite() {
+        if elo > 0.0 { "+inf".to_string() } else { "-inf".to_string() }
+    }
+    else {
+        // Anything that rounds to zero, including the -0 from an even score, should print as +0
+        format!("{:+.0}", elo.round() + 0.0)
+    }
+}
+
+/// The standings, followed by how each pair of entrants did against each other. Elo is measured
+/// against the rest of the field, so it's relative to the other entrants rather than absolute.
+pub fn report(entrants: &[Entrant], records: &[GameRecord]) -> String {
+    let count = entrants.len();
+    let mut totals = vec![Tally::default(); count];
+    let mut head_to_head = vec![vec![Tally::default(); count]; count];
+    let mut total_moves = vec![0; count];
+
+    for record in records {
+        for (player, opponent) in [(record.red, record.blue), (record.blue, record.red)] {
+            let tally = &mut head_to_head[player][opponent];
+            match record.winner() {
+                Some(winner) if winner == player => tally.wins += 1,
+                Some(_) => tally.losses += 1,
+                None => tally.draws += 1
+            }
+            total_moves[player] += record.result.moves;
+        }
+    }
+    for (player, row) in head_to_head.iter().enumerate() {
+        for tally in row {
+            totals[player].wins += tally.wins;
+            totals[player].draws += tally.draws;
+            totals[player].losses += tally.losses;
+        }
+    }
+
+    let name_width = entrants.iter().map(|entrant| entrant.name.len()).max().unwrap_or(0).max(6);
+    let average_length = records.iter().map(|record| record.result.moves).sum::<usize>() as f64 / records.len().max(1) as f64;
+    let mut report = format!("{} games, {:.1} moves long on average\n\n", records.len(), average_length);
+
+    // Best scores first
+    let mut order: Vec<usize> = (0..count).collect();
+    order.sort_by(|a, b| totals[*b].score().total_cmp(&totals[*a].score()));
+
+    report += &format!("{:<name_width$}  {:>5} {:>5} {:>5}  {:>6}  {:>9}  {:>5}  {}\n", "Bot", "W", "D", "L", "Score", "Avg moves", "Elo", "95% CI");
+    for &player in &order {
+        let tally = totals[player];
+        let (elo, low, high) = tally.elo();
+        report += &format!(
+            "{:<name_width$}  {:>5} {:>5} {:>5}  {:>5.1}%  {:>9.1}  {:>5}  [{}, {}]\n",
+            entrants[player].name, tally.wins, tally.draws, tally.losses, tally.score() * 100.0,
+            total_moves[player] as f64 / tally.games().max(1) as f64, format_elo(elo), format_elo(low), format_elo(high)
+        );
+    }
+
+    // Each cell is the row's wins, draws and losses against the column
+    report += &format!("\n{:<name_width$}", "W-D-L");
+    for &opponent in &order {
+        report += &format!("  {:>name_width$}", entrants[opponent].name);
+    }
+    report += "\n";
+    for &player in &order {
+        report += &format!("{:<name_width$}", entrants[player].name);
+        for &opponent in &order {
+            let cell = if player == opponent {
+                "-".to_string()
+            }
+            else {
+                let tally = head_to_head[player][opponent];
+                format!("{}-{}-{}", tally.wins, tally.draws, tally.losses)
+            };
+            report += &format!("  {:>name_width$}", cell);
+        }
+        report += "\n";
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn even_scores_have_no_sign_problems() {
+        assert_eq!(format_elo(elo_difference(0.5)), "+0");
+        assert_eq!(format_elo(-0.4), "+0");
+        assert_eq!(format_elo(elo_difference(0.75)), "+191");
+        assert_eq!(format_elo(elo_difference(0.25)), "-191");
+        assert_eq!(format_elo(elo_difference(0.0)), "-inf");
+    }
+}